@@ -1,5 +1,82 @@
 audio = Audio
+output-device = Output device
+equalizer = Equalizer
+normalize-volume = Normalize volume
+max-volume = Maximum volume
+max-volume-percent = {$percent}%
+color-balance = Color adjustments
+brightness = Brightness
+contrast = Contrast
+hue = Hue
+saturation = Saturation
+reset = Reset
+default-device = Default
+muted = Muted
+unmuted = Unmuted
+volume-percent = Volume: {$percent}%
+track-gain = Track gain: {$gain} dB
 subtitles = Subtitles
+subtitles-off = Off
+subtitle-position = Subtitle position
+subtitle-size = Subtitle size: {$size} pt
+subtitle-size-default = Subtitle size: Default
+subtitle-delay = Subtitle delay: {$delay} ms
+audio-delay = Audio delay: {$delay} ms
+seek-failed = Seeking is not supported for this media
+quality = Quality
+quality-auto = Automatic
+quality-variant = {$height}p, {$kbps} kbps
+quality-bitrate = {$kbps} kbps
+open-network-stream = Open network stream
+url-placeholder = Enter a URL, such as https://example.com/video.mp4
+invalid-url = Invalid URL: {$error}
+open = Open
+cancel = Cancel
+resumed = Resumed at {$time}
+finding-subtitles = Finding subtitles...
+subtitles-not-found = No subtitles found
+timestamp-copied = Copied {$time}
+frame-copied = Copied frame
+playback-finished = Playback finished
+replay = Replay
+no-video-open = No video open
+load-error = Failed to open {$url}: {$error}
+plugin-install-error = Required codec could not be installed: {$detail}
+installing-plugins = Installing required codecs...
+retry = Retry
+buffering = Buffering {$percent}%
+codec-notice = This file uses {$codec}, which may not play correctly
+timestamps-notice = This file has timestamps before its start, which may cause stuttering or failed seeks
+
+# Statistics overlay
+stats-resolution = Resolution: {$width}×{$height}
+stats-framerate = Frame rate: {$fps} fps
+stats-video-codec = Video codec: {$codec}
+stats-bitrate = Bitrate: {$kbps} kbps
+stats-sample-rate = Sample rate: {$rate} Hz
+stats-audio-codec = Audio codec: {$codec}
+stats-frames = Frames: {$rendered} rendered, {$dropped} dropped
+
+# A-B repeat
+loop-point-a = Loop start: {$time}
+loop-point-b = Loop end: {$time}
+loop-cleared = Loop cleared
+loop-on = Repeat on
+loop-off = Repeat off
+
+# Sleep timer
+sleep-timer-set = Pausing in {$time}
+sleep-timer-end-of-file = Pausing at the end of this file
+sleep-timer-cancelled = Sleep timer cancelled
+sleep-timer-expired = Sleep timer expired
+
+# Aspect modes
+aspect-mode = Aspect: {$mode}
+zoom-percent = Zoom: {$percent}%
+aspect-fit = Fit
+aspect-fill = Fill
+aspect-stretch = Stretch
+aspect-original = Original
 
 # Context Pages
 
@@ -13,11 +90,68 @@ match-desktop = Match desktop
 dark = Dark
 light = Light
 
+### General
+general = General
+controls-timeout = Hide controls after
+controls-timeout-seconds = {$seconds ->
+    [one] 1 second
+    *[other] {$seconds} seconds
+}
+controls-timeout-never = Never
+open-in-new-window = Open files in a new window
+scroll-seek = Seek with horizontal scrolling
+notify-on-finish = Notify when playback finishes
+pause-on-unfocus = Pause when the window loses focus
+title-progress = Show progress in window title
+
+### Rendering
+rendering = Rendering
+update-rate = Interface update rate
+update-rate-every-frame = Every frame
+update-rate-per-second = {$rate} per second
+hardware-decoding = Hardware video decoding
+styled-subtitles = Styled subtitles
+auto-rotate = Rotate using orientation tags
+tone-mapping = HDR tone mapping
+scrub-preview = Preview while seeking
+deinterlace = Deinterlace
+deinterlace-auto = Automatic
+deinterlace-on = On
+deinterlace-off = Off
+software-volume = Software volume
+software-color-balance = Software color balance
+visualization = Audio visualization
+
 # Menu
 
 ## File
 file = File
 open-media = Open media...
+open-network-stream-menu = Open network stream...
 open-recent-media = Open recent media
+open-containing-folder = Open containing folder
+load-subtitles = Load subtitles...
+find-subtitles = Find subtitles
+reload-file = Reload
 close-file = Close file
-quit = Quit
\ No newline at end of file
+quit = Quit
+
+## Playback
+playback = Playback
+stop = Stop
+copy-timestamp = Copy current timestamp
+copy-frame = Copy frame
+sleep-timer = Sleep timer
+sleep-timer-minutes = {$minutes} minutes
+sleep-timer-end-of-file-short = End of file
+sleep-timer-off = Off
+
+## View
+view = View
+menu-color-balance = Color adjustments...
+mini-player = Mini player
+zoom = Zoom
+zoom-in = Zoom in
+zoom-out = Zoom out
+zoom-reset = Reset zoom
+menu-settings = Settings...
\ No newline at end of file
@@ -5,8 +5,17 @@ use cosmic::{
     theme,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+use crate::Action;
 
 pub const CONFIG_VERSION: u64 = 1;
+pub const CONFIG_STATE_VERSION: u64 = 1;
+
+/// Most recent files kept in [`ConfigState::recent_files`]
+pub const RECENT_FILES_LIMIT: usize = 10;
+/// Most recent files kept in [`ConfigState::playback_positions`] and [`ConfigState::track_gains`]
+pub const FILE_STATE_LIMIT: usize = 200;
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum AppTheme {
@@ -25,16 +34,250 @@ impl AppTheme {
     }
 }
 
-#[derive(Clone, CosmicConfigEntry, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Deinterlace {
+    /// Deinterlace only content that is marked as interlaced
+    Auto,
+    On,
+    Off,
+}
+
+#[derive(Clone, CosmicConfigEntry, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default)]
 pub struct Config {
     pub app_theme: AppTheme,
+    /// Display name of the audio output device, or the default device if unset
+    pub audio_device: Option<String>,
+    /// Rotate videos using their orientation tags
+    pub auto_rotate: bool,
+    /// Brightness, contrast, hue, and saturation from -1 to 1, where zero is neutral
+    pub color_balance: [f64; 4],
+    /// Seconds without input before the controls are hidden, or zero to never hide them
+    pub controls_timeout: u64,
+    pub deinterlace: Deinterlace,
+    /// Gains in dB for the ten equalizer bands
+    pub equalizer: [f64; 10],
+    /// Prefer hardware video decoders such as VA-API and NVDEC when available
+    pub hardware_decoding: bool,
+    /// Key bindings added on top of the defaults, such as `"Ctrl+Shift+F": Fullscreen`
+    pub keybinds: HashMap<String, Action>,
+    /// Restart files from the beginning when they end
+    pub looping: bool,
+    /// Highest volume allowed, values above one amplify the audio
+    pub max_volume: f64,
+    /// Lowercase extensions of files that are opened from folders
+    pub media_extensions: Vec<String>,
+    /// Normalize loudness using ReplayGain tags
+    pub normalize_volume: bool,
+    /// Show a desktop notification when playback finishes
+    pub notify_on_finish: bool,
+    /// Open files in a new player window instead of replacing the current file
+    pub open_in_new_window: bool,
+    /// Pause when the window loses focus and resume when it regains focus
+    pub pause_on_unfocus: bool,
+    /// Language code of the audio stream to select when available
+    pub preferred_audio_language: Option<String>,
+    /// Language code of the subtitle stream to select when available
+    pub preferred_text_language: Option<String>,
+    /// Levels of subdirectories to search when opening a folder
+    pub recursive_scan_depth: u32,
+    /// Seek with horizontal scrolling over the video, vertical scrolling always changes volume
+    pub scroll_seek: bool,
+    /// Seconds to seek per line of horizontal scrolling
+    pub scroll_seek_step: f64,
+    /// Update the video while dragging the seek slider
+    pub scrub_preview: bool,
+    /// Seconds to seek with Ctrl and the arrow keys
+    pub seek_step_coarse: f64,
+    /// Seconds to seek with Shift and the arrow keys
+    pub seek_step_fine: f64,
+    /// Open any file from folders instead of only media files
+    pub show_all_files: bool,
+    pub soft_colorbalance: bool,
+    pub soft_volume: bool,
+    /// Render SSA/ASS subtitles with their own fonts, colors and positions
+    pub styled_subtitles: bool,
+    /// Text color of plain subtitles as ARGB
+    pub subtitle_color: u32,
+    /// Shell command that finds subtitles for the current file, such as an OpenSubtitles script.
+    /// It is run with the media path or URL and the OpenSubtitles hash as `$1` and `$2`, where
+    /// the hash is empty for remote files. On success it exits with status zero and prints the
+    /// path of a subtitle file on the first line of its output, within 60 seconds.
+    pub subtitle_command: Option<String>,
+    /// Pango font description used for plain subtitles. Its family is also the fallback for
+    /// SSA/ASS subtitles that use fonts which are neither installed nor attached, once the
+    /// player is restarted after it is first set.
+    pub subtitle_font: Option<String>,
+    /// Outline color of plain subtitles as ARGB
+    pub subtitle_outline_color: u32,
+    /// Distance in pixels between the subtitles and the bottom of the video
+    pub subtitle_position: i32,
+    /// Font size in points for plain subtitles, or zero to use the size from the font description
+    pub subtitle_size: u32,
+    /// Show subtitles when a file is loaded, updated when subtitles are turned on or off
+    pub subtitles_enabled: bool,
+    /// Show the playback progress percentage in the window title
+    pub title_progress: bool,
+    /// Map HDR video to SDR with vapostproc when it is available
+    pub tone_mapping: bool,
+    /// Position updates per second, or zero to update on every frame
+    pub update_rate: u32,
+    pub visualization: bool,
+    /// Volume change per line of vertical scrolling, where one is full volume
+    pub volume_scroll_step: f64,
+    pub window_height: u32,
+    pub window_width: u32,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             app_theme: AppTheme::System,
+            audio_device: None,
+            auto_rotate: true,
+            color_balance: [0.0; 4],
+            controls_timeout: 2,
+            deinterlace: Deinterlace::Auto,
+            equalizer: [0.0; 10],
+            hardware_decoding: true,
+            keybinds: HashMap::new(),
+            looping: false,
+            max_volume: 1.0,
+            media_extensions: [
+                "3gp", "avi", "flac", "flv", "m2ts", "m4a", "m4v", "mka", "mkv", "mov", "mp3",
+                "mp4", "mpeg", "mpg", "oga", "ogg", "ogv", "opus", "ts", "wav", "webm", "wmv",
+            ]
+            .iter()
+            .map(|extension| extension.to_string())
+            .collect(),
+            normalize_volume: false,
+            notify_on_finish: false,
+            open_in_new_window: false,
+            pause_on_unfocus: false,
+            preferred_audio_language: None,
+            preferred_text_language: None,
+            recursive_scan_depth: 0,
+            scroll_seek: true,
+            scroll_seek_step: 5.0,
+            scrub_preview: true,
+            seek_step_coarse: 60.0,
+            seek_step_fine: 1.0,
+            show_all_files: false,
+            soft_colorbalance: true,
+            soft_volume: true,
+            styled_subtitles: true,
+            subtitle_color: 0xffffffff,
+            subtitle_command: None,
+            subtitle_font: None,
+            subtitle_outline_color: 0xff000000,
+            subtitle_position: 25,
+            subtitle_size: 0,
+            subtitles_enabled: true,
+            title_progress: false,
+            tone_mapping: false,
+            update_rate: 0,
+            visualization: false,
+            volume_scroll_step: 0.0125,
+            window_height: 768,
+            window_width: 1024,
+        }
+    }
+}
+
+#[derive(Clone, CosmicConfigEntry, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ConfigState {
+    /// The mini player was in use last, so it is restored on startup
+    pub mini_player: bool,
+    /// Positions in seconds to resume unfinished files from, most recently saved first
+    pub playback_positions: VecDeque<(url::Url, f64)>,
+    /// Recently opened files, newest first
+    pub recent_files: VecDeque<RecentFile>,
+    /// Gains in dB applied on top of the volume for specific files, most recently set first
+    pub track_gains: VecDeque<(url::Url, f64)>,
+}
+
+impl ConfigState {
+    pub fn playback_position(&self, url: &url::Url) -> Option<f64> {
+        file_value(&self.playback_positions, url)
+    }
+
+    /// Saves or forgets the position of a file, returning true if it changed
+    pub fn set_playback_position(&mut self, url: &url::Url, position_opt: Option<f64>) -> bool {
+        set_file_value(&mut self.playback_positions, url, position_opt)
+    }
+
+    pub fn track_gain(&self, url: &url::Url) -> Option<f64> {
+        file_value(&self.track_gains, url)
+    }
+
+    /// Saves or forgets the gain of a file, returning true if it changed
+    pub fn set_track_gain(&mut self, url: &url::Url, gain_opt: Option<f64>) -> bool {
+        set_file_value(&mut self.track_gains, url, gain_opt)
+    }
+}
+
+fn file_value(values: &VecDeque<(url::Url, f64)>, url: &url::Url) -> Option<f64> {
+    values
+        .iter()
+        .find(|(value_url, _)| value_url == url)
+        .map(|&(_, value)| value)
+}
+
+/// Moves the value of a file to the front, dropping the oldest beyond [`FILE_STATE_LIMIT`]
+fn set_file_value(
+    values: &mut VecDeque<(url::Url, f64)>,
+    url: &url::Url,
+    value_opt: Option<f64>,
+) -> bool {
+    let old_opt = file_value(values, url);
+    values.retain(|(value_url, _)| value_url != url);
+    if let Some(value) = value_opt {
+        values.push_front((url.clone(), value));
+        values.truncate(FILE_STATE_LIMIT);
+    }
+    old_opt != value_opt
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RecentFile {
+    pub url: url::Url,
+    /// Title from the media tags, if it had one
+    pub title_opt: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(index: usize) -> url::Url {
+        url::Url::parse(&format!("file:///videos/{index}.mkv")).unwrap()
+    }
+
+    #[test]
+    fn playback_positions_keep_newest() {
+        let mut state = ConfigState::default();
+        for index in 0..FILE_STATE_LIMIT + 5 {
+            assert!(state.set_playback_position(&url(index), Some(60.0)));
         }
+        // Saving an old file again keeps it
+        assert!(state.set_playback_position(&url(5), Some(90.0)));
+        assert!(state.set_playback_position(&url(FILE_STATE_LIMIT + 5), Some(60.0)));
+
+        assert_eq!(state.playback_positions.len(), FILE_STATE_LIMIT);
+        assert_eq!(state.playback_position(&url(5)), Some(90.0));
+        assert_eq!(state.playback_position(&url(6)), None);
+        assert_eq!(state.playback_position(&url(7)), Some(60.0));
+    }
+
+    #[test]
+    fn file_values_report_changes() {
+        let mut state = ConfigState::default();
+        assert!(state.set_track_gain(&url(0), Some(3.0)));
+        assert!(!state.set_track_gain(&url(0), Some(3.0)));
+        assert_eq!(state.track_gain(&url(0)), Some(3.0));
+        assert!(state.set_track_gain(&url(0), None));
+        assert!(!state.set_track_gain(&url(0), None));
+        assert!(state.track_gains.is_empty());
     }
 }
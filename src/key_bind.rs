@@ -5,8 +5,84 @@ use crate::Action;
 
 pub use cosmic::widget::menu::key_bind::{KeyBind, Modifier};
 
-//TODO: load from config
-pub fn key_binds() -> HashMap<KeyBind, Action> {
+/// Parses a key binding such as `"Ctrl+Shift+F"`, `"Alt+Enter"`, or `"Space"`.
+///
+/// Modifiers are `Super`, `Ctrl`, `Alt`, and `Shift`, and must come before the key. The key is
+/// either a single character or one of the named keys below, matched case-insensitively.
+/// Modifiers may be repeated or given in any order.
+fn parse_key_bind(key_bind: &str) -> Option<KeyBind> {
+    let mut parts: Vec<&str> = key_bind.split('+').map(|part| part.trim()).collect();
+    // A trailing plus is the plus key itself
+    if key_bind.ends_with("++") || key_bind == "+" {
+        parts.retain(|part| !part.is_empty());
+        parts.push("+");
+    }
+    let key_part = parts.pop()?;
+
+    let mut parsed = Vec::with_capacity(parts.len());
+    for part in parts {
+        parsed.push(match part.to_lowercase().as_str() {
+            "super" | "logo" => Modifier::Super,
+            "ctrl" | "control" => Modifier::Ctrl,
+            "alt" => Modifier::Alt,
+            "shift" => Modifier::Shift,
+            _ => return None,
+        });
+    }
+    // Use the order of the default bindings, so equal bindings replace each other
+    let modifiers = [
+        Modifier::Super,
+        Modifier::Ctrl,
+        Modifier::Alt,
+        Modifier::Shift,
+    ]
+    .into_iter()
+    .filter(|modifier| parsed.contains(modifier))
+    .collect();
+
+    let key = match key_part.to_lowercase().as_str() {
+        "space" => Key::Named(Named::Space),
+        "enter" | "return" => Key::Named(Named::Enter),
+        "escape" | "esc" => Key::Named(Named::Escape),
+        "tab" => Key::Named(Named::Tab),
+        "backspace" => Key::Named(Named::Backspace),
+        "delete" => Key::Named(Named::Delete),
+        "insert" => Key::Named(Named::Insert),
+        "home" => Key::Named(Named::Home),
+        "end" => Key::Named(Named::End),
+        "pageup" => Key::Named(Named::PageUp),
+        "pagedown" => Key::Named(Named::PageDown),
+        "left" => Key::Named(Named::ArrowLeft),
+        "right" => Key::Named(Named::ArrowRight),
+        "up" => Key::Named(Named::ArrowUp),
+        "down" => Key::Named(Named::ArrowDown),
+        "f1" => Key::Named(Named::F1),
+        "f2" => Key::Named(Named::F2),
+        "f3" => Key::Named(Named::F3),
+        "f4" => Key::Named(Named::F4),
+        "f5" => Key::Named(Named::F5),
+        "f6" => Key::Named(Named::F6),
+        "f7" => Key::Named(Named::F7),
+        "f8" => Key::Named(Named::F8),
+        "f9" => Key::Named(Named::F9),
+        "f10" => Key::Named(Named::F10),
+        "f11" => Key::Named(Named::F11),
+        "f12" => Key::Named(Named::F12),
+        "mediaplaypause" => Key::Named(Named::MediaPlayPause),
+        "mediastop" => Key::Named(Named::MediaStop),
+        "mediarewind" => Key::Named(Named::MediaRewind),
+        "mediafastforward" => Key::Named(Named::MediaFastForward),
+        "mediatracknext" => Key::Named(Named::MediaTrackNext),
+        "mediatrackprevious" => Key::Named(Named::MediaTrackPrevious),
+        lower if lower.chars().count() == 1 => Key::Character(lower.into()),
+        _ => return None,
+    };
+
+    Some(KeyBind { modifiers, key })
+}
+
+/// Default key bindings, with the bindings from the config added on top
+pub fn key_binds(custom: &HashMap<String, Action>) -> HashMap<KeyBind, Action> {
     let mut key_binds = HashMap::new();
 
     macro_rules! bind {
@@ -22,11 +98,127 @@ pub fn key_binds() -> HashMap<KeyBind, Action> {
     }
 
     //TODO: key bindings
+    bind!([], Key::Character("a".into()), CycleAspect);
+    bind!([Ctrl], Key::Character("c".into()), CopyTimestamp);
+    bind!([Ctrl, Shift], Key::Character("c".into()), CopyFrame);
     bind!([], Key::Character("f".into()), Fullscreen);
     bind!([Alt], Key::Named(Named::Enter), Fullscreen);
+    bind!([Ctrl], Key::Character("m".into()), MiniPlayer);
+    bind!([], Key::Named(Named::F5), Reload);
+    bind!([], Key::Character("i".into()), ToggleStats);
+    bind!([], Key::Character("j".into()), AudioDelayDecrease);
+    bind!([], Key::Character("k".into()), AudioDelayIncrease);
+    bind!([], Key::Character("l".into()), ToggleLoop);
+    bind!([], Key::Character("m".into()), Mute);
+    bind!([], Key::Character("s".into()), Screenshot);
     bind!([], Key::Named(Named::Space), PlayPause);
+    bind!([], Key::Named(Named::MediaPlayPause), PlayPause);
+    bind!([], Key::Named(Named::MediaStop), Stop);
+    bind!([], Key::Named(Named::MediaRewind), SeekBackward);
+    bind!([], Key::Named(Named::MediaFastForward), SeekForward);
     bind!([], Key::Named(Named::ArrowLeft), SeekBackward);
     bind!([], Key::Named(Named::ArrowRight), SeekForward);
+    bind!([Shift], Key::Named(Named::ArrowLeft), SeekBackwardFine);
+    bind!([Shift], Key::Named(Named::ArrowRight), SeekForwardFine);
+    bind!([Ctrl], Key::Named(Named::ArrowLeft), SeekBackwardCoarse);
+    bind!([Ctrl], Key::Named(Named::ArrowRight), SeekForwardCoarse);
+    bind!([], Key::Named(Named::ArrowUp), VolumeUp);
+    bind!([], Key::Named(Named::ArrowDown), VolumeDown);
+    bind!([], Key::Character("[".into()), SetLoopPointA);
+    bind!([], Key::Character("]".into()), SetLoopPointB);
+    bind!([], Key::Character("\\".into()), ClearLoopPoints);
+    bind!([Ctrl], Key::Character("=".into()), ZoomIn);
+    bind!([Ctrl], Key::Character("-".into()), ZoomOut);
+    bind!([Ctrl], Key::Character("0".into()), ZoomReset);
+    bind!([], Key::Character("z".into()), SubtitleDelayDecrease);
+    bind!([], Key::Character("x".into()), SubtitleDelayIncrease);
+
+    for (key_bind_str, action) in custom.iter() {
+        match parse_key_bind(key_bind_str) {
+            Some(key_bind) => {
+                key_binds.insert(key_bind, *action);
+            }
+            None => {
+                log::warn!("invalid key binding {:?} for {:?}", key_bind_str, action);
+            }
+        }
+    }
 
     key_binds
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_bind(modifiers: Vec<Modifier>, key: Key) -> KeyBind {
+        KeyBind { modifiers, key }
+    }
+
+    #[test]
+    fn parse_valid_key_binds() {
+        assert_eq!(
+            parse_key_bind("Ctrl+Shift+F"),
+            Some(key_bind(
+                vec![Modifier::Ctrl, Modifier::Shift],
+                Key::Character("f".into())
+            ))
+        );
+        assert_eq!(
+            parse_key_bind("alt + enter"),
+            Some(key_bind(vec![Modifier::Alt], Key::Named(Named::Enter)))
+        );
+        assert_eq!(
+            parse_key_bind("Space"),
+            Some(key_bind(Vec::new(), Key::Named(Named::Space)))
+        );
+        assert_eq!(
+            parse_key_bind("Ctrl++"),
+            Some(key_bind(vec![Modifier::Ctrl], Key::Character("+".into())))
+        );
+        assert_eq!(
+            parse_key_bind("MediaPlayPause"),
+            Some(key_bind(Vec::new(), Key::Named(Named::MediaPlayPause)))
+        );
+    }
+
+    #[test]
+    fn parse_modifier_order_and_duplicates() {
+        let expected = parse_key_bind("Ctrl+Shift+C");
+        assert!(expected.is_some());
+        assert_eq!(parse_key_bind("Shift+Ctrl+C"), expected);
+        assert_eq!(parse_key_bind("Ctrl+Ctrl+Shift+C"), expected);
+        assert_eq!(parse_key_bind("control+shift+shift+c"), expected);
+    }
+
+    #[test]
+    fn parse_invalid_key_binds() {
+        assert_eq!(parse_key_bind(""), None);
+        assert_eq!(parse_key_bind("   "), None);
+        assert_eq!(parse_key_bind("Ctrl+"), None);
+        assert_eq!(parse_key_bind("Hyper+F"), None);
+        assert_eq!(parse_key_bind("Ctrl+Banana"), None);
+        assert_eq!(parse_key_bind("F13"), None);
+    }
+
+    #[test]
+    fn custom_key_binds_replace_defaults() {
+        let custom = HashMap::from([
+            ("Shift+Ctrl+C".to_string(), Action::Screenshot),
+            ("Ctrl+Nope".to_string(), Action::Fullscreen),
+            (String::new(), Action::Mute),
+        ]);
+        let defaults = key_binds(&HashMap::new());
+        let key_binds = key_binds(&custom);
+
+        // Invalid bindings are skipped without affecting the defaults
+        assert_eq!(key_binds.len(), defaults.len());
+        assert_eq!(
+            key_binds.get(&key_bind(
+                vec![Modifier::Ctrl, Modifier::Shift],
+                Key::Character("c".into())
+            )),
+            Some(&Action::Screenshot)
+        );
+    }
+}
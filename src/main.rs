@@ -6,13 +6,16 @@ use cosmic::{
     cosmic_config::{self, CosmicConfigEntry},
     cosmic_theme, executor, font,
     iced::{
+        clipboard::{self, mime::AsMimeTypes},
         event::{self, Event},
-        keyboard::{Event as KeyEvent, Key, Modifiers},
-        mouse::Event as MouseEvent,
+        keyboard::{key::Named, Event as KeyEvent, Key, Modifiers},
+        mouse::{Event as MouseEvent, ScrollDelta},
         subscription::Subscription,
-        window, Alignment, Background, Border, Color, Length, Limits,
+        time,
+        widget::vertical_slider,
+        window, Alignment, Background, Border, Color, ContentFit, Length, Limits, Point, Size,
     },
-    theme,
+    iced_widget, theme,
     widget::{self, menu::action::MenuAction, Slider},
     Application, ApplicationExt, Element,
 };
@@ -20,29 +23,150 @@ use iced_video_player::{
     gst::{self, prelude::*},
     gst_app, gst_pbutils, Video, VideoPlayer,
 };
+use serde::{Deserialize, Serialize};
 use std::{
     any::TypeId,
+    borrow::Cow,
     collections::HashMap,
+    env,
     ffi::{CStr, CString},
-    fs, process, thread,
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
     time::{Duration, Instant},
 };
 
 use crate::{
-    config::{Config, CONFIG_VERSION},
+    config::{
+        AppTheme, Config, ConfigState, Deinterlace, RecentFile, CONFIG_STATE_VERSION,
+        CONFIG_VERSION, RECENT_FILES_LIMIT,
+    },
     key_bind::{key_binds, KeyBind},
+    manifest::StreamVariant,
 };
 
 mod config;
 mod key_bind;
 mod localize;
+mod manifest;
+mod matroska;
 mod menu;
+mod probe;
+
+static OSD_TIMEOUT: Duration = Duration::new(2, 0);
+static DURATION_INTERVAL: Duration = Duration::new(1, 0);
+static SCRUB_INTERVAL: Duration = Duration::from_millis(100);
+static POSITION_SAVE_INTERVAL: Duration = Duration::new(10, 0);
+static WINDOW_SAVE_DELAY: Duration = Duration::from_millis(500);
+static SUBTITLE_COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+/// Time a finished notification waits for an action before it is withdrawn
+#[cfg(feature = "xdg-portal")]
+static NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Files are only resumed after this many seconds, and not within this many seconds of the end
+const RESUME_MARGIN: f64 = 30.0;
+
+const SEEK_SLIDER_HEIGHT: f32 = 24.0;
+const SEEK_MARKER_HEIGHT: f32 = 14.0;
+
+/// Position update rates per second, zero updates on every frame
+const UPDATE_RATES: [u32; 5] = [0, 30, 15, 10, 5];
+const CONTROLS_TIMEOUTS: [u64; 6] = [1, 2, 3, 5, 10, 0];
+
+/// Maximum volume choices, above one is amplified by the audio filter
+const MAX_VOLUMES: [f64; 3] = [1.0, 1.5, 2.0];
+
+/// Largest per-file gain in dB, in either direction
+const MAX_TRACK_GAIN: f64 = 12.0;
+
+/// Largest subtitle font size in points
+const MAX_SUBTITLE_SIZE: u32 = 72;
+
+/// Largest zoom factor, where a quarter of the width and height is shown
+const MAX_ZOOM: f64 = 4.0;
+/// Fraction of the hidden area panned per key press
+const PAN_STEP: f64 = 0.1;
+
+/// Window size in the mini player, which has no header bar
+const MINI_PLAYER_WIDTH: f32 = 480.0;
+const MINI_PLAYER_HEIGHT: f32 = 270.0;
+
+/// Largest audio delay in either direction, in milliseconds
+const MAX_AUDIO_DELAY: i64 = 2000;
+
+/// Center frequencies of the equalizer-10bands element
+const EQUALIZER_BANDS: [&str; 10] = [
+    "29", "59", "119", "237", "474", "947", "1.9k", "3.8k", "7.5k", "15k",
+];
+
+/// Labels of the playbin color balance channels, in the order stored in the config
+const COLOR_BALANCE_CHANNELS: [&str; 4] = ["BRIGHTNESS", "CONTRAST", "HUE", "SATURATION"];
+
+/// Extensions of subtitle files that are loaded automatically alongside videos
+const SUBTITLE_EXTENSIONS: [&str; 5] = ["srt", "ass", "ssa", "vtt", "sub"];
+
+#[cfg(feature = "xdg-portal")]
+const FINISHED_NOTIFICATION_ID: &str = "playback-finished";
 
-static CONTROLS_TIMEOUT: Duration = Duration::new(2, 0);
+const ADAPTIVE_DEMUXERS: [&str; 5] = [
+    "dashdemux",
+    "dashdemux2",
+    "hlsdemux",
+    "hlsdemux2",
+    "mssdemux",
+];
+/// Largest adaptive stream manifest copied to list its variants
+const MAX_MANIFEST_SIZE: usize = 4 * 1024 * 1024;
 
 const GST_PLAY_FLAG_VIDEO: i32 = 1 << 0;
 const GST_PLAY_FLAG_AUDIO: i32 = 1 << 1;
 const GST_PLAY_FLAG_TEXT: i32 = 1 << 2;
+const GST_PLAY_FLAG_VIS: i32 = 1 << 3;
+const GST_PLAY_FLAG_SOFT_VOLUME: i32 = 1 << 4;
+const GST_PLAY_FLAG_DEINTERLACE: i32 = 1 << 9;
+const GST_PLAY_FLAG_SOFT_COLORBALANCE: i32 = 1 << 10;
+
+/// Font attachment mime types that every assrender version loads
+const ASS_FONT_MIME_TYPES: [&str; 3] = [
+    "application/x-font-ttf",
+    "application/x-font-otf",
+    "application/x-truetype-font",
+];
+/// File extensions of font attachments
+const FONT_EXTENSIONS: [&str; 4] = ["ttf", "otf", "ttc", "otc"];
+/// Words that end a Pango font family, such as styles and stretches
+const FONT_STYLE_WORDS: [&str; 25] = [
+    "normal",
+    "roman",
+    "italic",
+    "oblique",
+    "thin",
+    "ultra-light",
+    "extra-light",
+    "light",
+    "semi-light",
+    "book",
+    "regular",
+    "medium",
+    "semi-bold",
+    "bold",
+    "ultra-bold",
+    "extra-bold",
+    "heavy",
+    "black",
+    "small-caps",
+    "ultra-condensed",
+    "extra-condensed",
+    "condensed",
+    "semi-condensed",
+    "semi-expanded",
+    "expanded",
+];
 
 fn language_name(code: &str) -> Option<String> {
     let code_c = CString::new(code).ok()?;
@@ -58,311 +182,3038 @@ fn language_name(code: &str) -> Option<String> {
     Some(name.to_string())
 }
 
-/// Runs application with these settings
-#[rustfmt::skip]
-pub fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
-
-    localize::localize();
-
-    let (config_handler, config) = match cosmic_config::Config::new(App::APP_ID, CONFIG_VERSION) {
-        Ok(config_handler) => {
-            let config = match Config::get_entry(&config_handler) {
-                Ok(ok) => ok,
-                Err((errs, config)) => {
-                    log::info!("errors loading config: {:?}", errs);
-                    config
-                }
-            };
-            (Some(config_handler), config)
-        }
-        Err(err) => {
-            log::error!("failed to create config handler: {}", err);
-            (None, Config::default())
-        }
+/// Configures elements as they are added to the playbin pipeline
+fn element_setup(config: &Config, element: &gst::Element) {
+    let Some(factory) = element.factory() else {
+        return;
     };
-
-    let mut settings = Settings::default();
-    settings = settings.theme(config.app_theme.theme());
-    settings = settings.size_limits(Limits::NONE.min_width(360.0).min_height(180.0));
-
-    let url_opt = match std::env::args().nth(1) {
-        Some(arg) => match url::Url::parse(&arg) {
-            Ok(url) => Some(url),
-            Err(_) => match fs::canonicalize(&arg) {
-                Ok(path) => match url::Url::from_file_path(&path) {
-                    Ok(url) => Some(url),
-                    Err(()) => {
-                        log::warn!("failed to parse argument {:?}", arg);
-                        None
-                    }
+    match factory.name().as_str() {
+        "deinterlace" => {
+            element.set_property_from_str(
+                "mode",
+                match config.deinterlace {
+                    Deinterlace::Auto => "auto",
+                    Deinterlace::On => "interlaced",
+                    Deinterlace::Off => "disabled",
                 },
-                Err(_) => {
-                    log::warn!("failed to parse argument {:?}", arg);
-                    None
-                }
-            },
-        },
-        None => None,
-    };
-
-    let flags = Flags {
-        config_handler,
-        config,
-        url_opt,
-    };
-    cosmic::app::run::<App>(settings, flags)?;
+            );
+        }
+        "textoverlay" => {
+            element.set_property("ypad", config.subtitle_position);
+            element.set_property("color", config.subtitle_color);
+            element.set_property("outline-color", config.subtitle_outline_color);
+        }
+        _ => {
+            let klass = factory.klass();
+            if klass.contains("Decoder") && klass.contains("Video") {
+                log::info!("using video decoder {}", factory.name());
+            }
+        }
+    }
+}
 
-    Ok(())
+/// Returns the start of the video sink that tone maps HDR video, if enabled and available
+fn tone_mapping_element(config: &Config) -> &'static str {
+    if !config.tone_mapping {
+        return "";
+    }
+    if gst::ElementFactory::find("vapostproc").is_none() {
+        log::warn!("vapostproc not found, HDR video will not be tone mapped");
+        return "";
+    }
+    "vapostproc hdr-tone-mapping=true ! "
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub enum Action {
-    FileClose,
-    FileOpen,
-    Fullscreen,
-    PlayPause,
-    SeekBackward,
-    SeekForward,
-    WindowClose,
+/// Returns true if the video uses an HDR transfer function
+fn is_hdr(pipeline: &gst::Pipeline) -> bool {
+    let info_opt = pipeline
+        .emit_by_name::<Option<gst::Pad>>("get-video-pad", &[&0i32])
+        .and_then(|pad| pad.current_caps())
+        .and_then(|caps| gstreamer_video::VideoInfo::from_caps(&caps).ok());
+    info_opt.is_some_and(|info| {
+        matches!(
+            info.colorimetry().transfer(),
+            gstreamer_video::VideoTransferFunction::Smpte2084
+                | gstreamer_video::VideoTransferFunction::AribStdB67
+        )
+    })
 }
 
-impl MenuAction for Action {
-    type Message = Message;
+/// Returns the videoflip method, which follows orientation tags unless auto rotation is off
+fn flip_method(config: &Config) -> &'static str {
+    if config.auto_rotate {
+        "automatic"
+    } else {
+        "none"
+    }
+}
 
-    fn message(&self) -> Message {
-        match self {
-            Self::FileClose => Message::FileClose,
-            Self::FileOpen => Message::FileOpen,
-            Self::Fullscreen => Message::Fullscreen,
-            Self::PlayPause => Message::PlayPause,
-            Self::SeekBackward => Message::SeekRelative(-10.0),
-            Self::SeekForward => Message::SeekRelative(10.0),
-            Self::WindowClose => Message::WindowClose,
+/// Returns the Pango font description for plain subtitles, if any is configured
+fn subtitle_font_desc(config: &Config) -> Option<String> {
+    match (&config.subtitle_font, config.subtitle_size) {
+        (None, 0) => None,
+        (Some(font), 0) => Some(font.clone()),
+        // Pango only reads a size at the end, so replace the one in the description
+        (font_opt, size) => {
+            let font = font_opt.as_deref().map_or("", without_font_size);
+            Some(format!(
+                "{} {}",
+                if font.is_empty() { "Sans" } else { font },
+                size
+            ))
         }
     }
 }
 
-#[derive(Clone)]
-pub struct Flags {
-    config_handler: Option<cosmic_config::Config>,
-    config: Config,
-    url_opt: Option<url::Url>,
+/// Returns a Pango font description without a trailing size such as `12` or `16px`
+fn without_font_size(font_desc: &str) -> &str {
+    let font_desc = font_desc.trim_end();
+    match font_desc.rsplit_once(char::is_whitespace) {
+        Some((rest, size)) if size.trim_end_matches("px").parse::<f64>().is_ok() => rest.trim_end(),
+        None if font_desc.trim_end_matches("px").parse::<f64>().is_ok() => "",
+        _ => font_desc,
+    }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub enum DropdownKind {
-    Audio,
-    Subtitle,
-}
+/// Ranks hardware video decoders above software decoders, or restores their original ranks
+fn set_hardware_decoding(enabled: bool) {
+    // Ranks of hardware decoders before they were changed, by factory name
+    static ORIGINAL_RANKS: Mutex<Option<HashMap<String, gst::Rank>>> = Mutex::new(None);
 
-/// Messages that are used specifically by our [`App`].
-#[derive(Clone, Debug)]
-pub enum Message {
-    None,
-    Config(Config),
-    DropdownToggle(DropdownKind),
-    FileClose,
-    FileLoad(url::Url),
-    FileOpen,
-    Fullscreen,
-    Key(Modifiers, Key),
-    AudioCode(usize),
-    AudioToggle,
-    AudioVolume(f64),
-    TextCode(usize),
-    PlayPause,
-    Seek(f64),
-    SeekRelative(f64),
-    SeekRelease,
-    EndOfStream,
-    MissingPlugin(gst::Message),
-    NewFrame,
-    Reload,
-    ShowControls,
-    SystemThemeModeChange(cosmic_theme::ThemeMode),
-    WindowClose,
+    let Ok(mut original_ranks) = ORIGINAL_RANKS.lock() else {
+        return;
+    };
+    let original_ranks = original_ranks.get_or_insert_with(HashMap::new);
+    let registry = gst::Registry::get();
+    for feature in registry.features(gst::ElementFactory::static_type()) {
+        let Ok(factory) = feature.downcast::<gst::ElementFactory>() else {
+            continue;
+        };
+        let klass = factory.klass();
+        if klass.contains("Decoder") && klass.contains("Video") && klass.contains("Hardware") {
+            let original_rank = *original_ranks
+                .entry(factory.name().to_string())
+                .or_insert_with(|| factory.rank());
+            // Decoders shipped without a rank are disabled on purpose, and software decoders
+            // are used if a hardware decoder fails to negotiate
+            factory.set_rank(if enabled && original_rank > gst::Rank::NONE {
+                original_rank.max(gst::Rank::PRIMARY + 1)
+            } else {
+                original_rank
+            });
+        }
+    }
 }
 
-/// The [`App`] stores application-specific state.
-pub struct App {
-    core: Core,
-    flags: Flags,
-    controls: bool,
-    controls_time: Instant,
-    dropdown_opt: Option<DropdownKind>,
-    fullscreen: bool,
-    key_binds: HashMap<KeyBind, Action>,
-    video_opt: Option<Video>,
-    position: f64,
-    duration: f64,
-    dragging: bool,
-    audio_codes: Vec<String>,
-    current_audio: i32,
-    text_codes: Vec<String>,
-    current_text: i32,
+/// Lets subtitleoverlay pick assrender for SSA/ASS subtitles, or forces plain text rendering
+fn set_styled_subtitles(enabled: bool) {
+    let Some(factory) = gst::ElementFactory::find("assrender") else {
+        if enabled {
+            log::warn!("assrender not found, SSA/ASS subtitles will be rendered as plain text");
+        }
+        return;
+    };
+    factory.set_rank(if enabled {
+        gst::Rank::PRIMARY
+    } else {
+        gst::Rank::NONE
+    });
 }
 
-impl App {
-    fn close(&mut self) {
-        //TODO: drop does not work well
-        if let Some(mut video) = self.video_opt.take() {
-            log::info!("pausing video");
-            video.set_paused(true);
-            log::info!("dropping video");
-            drop(video);
-            log::info!("dropped video");
+/// Returns true if an attachment sample holds a font, by mime type or file name
+fn is_font_attachment(sample: &gst::Sample) -> bool {
+    let mime_type_opt = sample
+        .caps()
+        .and_then(|caps| caps.structure(0))
+        .map(|structure| structure.name().as_str());
+    if let Some(mime_type) = mime_type_opt {
+        if mime_type.starts_with("font/")
+            || mime_type.contains("-font")
+            || mime_type.contains("truetype")
+            || mime_type.contains("opentype")
+        {
+            return true;
         }
-        self.position = 0.0;
-        self.duration = 0.0;
-        self.dragging = false;
-        self.audio_codes = Vec::new();
-        self.current_audio = -1;
-        self.text_codes = Vec::new();
-        self.current_text = -1;
     }
+    let filename_opt = sample
+        .info()
+        .and_then(|info| info.get::<&str>("filename").ok());
+    filename_opt.is_some_and(|filename| {
+        Path::new(filename)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                FONT_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+            })
+    })
+}
 
-    fn load(&mut self) -> Command<Message> {
-        self.close();
-
-        let url = match &self.flags.url_opt {
-            Some(some) => some,
-            None => return Command::none(),
-        };
-
-        log::info!("Loading {}", url);
-
-        //TODO: this code came from iced_video_player::Video::new and has been modified to stop the pipeline on error
-        //TODO: remove unwraps and enable playback of files with only audio.
-        let video = {
-            gst::init().unwrap();
-
-            let pipeline = format!(
-                "playbin uri=\"{}\" video-sink=\"videoscale ! videoconvert ! appsink name=iced_video drop=true caps=video/x-raw,format=NV12,pixel-aspect-ratio=1/1\"",
-                url.as_str()
-            );
-            let pipeline = gst::parse::launch(pipeline.as_ref())
-                .unwrap()
-                .downcast::<gst::Pipeline>()
-                .map_err(|_| iced_video_player::Error::Cast)
-                .unwrap();
-
-            let video_sink: gst::Element = pipeline.property("video-sink");
-            let pad = video_sink.pads().first().cloned().unwrap();
-            let pad = pad.dynamic_cast::<gst::GhostPad>().unwrap();
-            let bin = pad
-                .parent_element()
-                .unwrap()
-                .downcast::<gst::Bin>()
-                .unwrap();
-            let video_sink = bin.by_name("iced_video").unwrap();
-            let video_sink = video_sink.downcast::<gst_app::AppSink>().unwrap();
-
-            match Video::from_gst_pipeline(pipeline.clone(), video_sink, None) {
-                Ok(ok) => ok,
-                Err(err) => {
-                    log::warn!("failed to open {}: {err}", url);
-                    pipeline.set_state(gst::State::Null).unwrap();
-                    return Command::none();
-                }
-            }
+/// Returns a copy of the tags with font attachments given a mime type that assrender loads,
+/// or `None` if no attachment needed it
+fn normalize_font_attachments(tags: &gst::TagListRef) -> Option<gst::TagList> {
+    let mut changed = false;
+    let mut samples = Vec::new();
+    for i in 0..tags.size_by_name("attachment") {
+        let Some(sample) = tags
+            .index_generic("attachment", i)
+            .and_then(|value| value.get::<gst::Sample>().ok())
+        else {
+            continue;
         };
-
-        self.duration = video.duration().as_secs_f64();
-        let pipeline = video.pipeline();
-        self.video_opt = Some(video);
-
-        let n_audio = pipeline.property::<i32>("n-audio");
-        self.audio_codes = Vec::with_capacity(n_audio as usize);
-        for i in 0..n_audio {
-            let tags: gst::TagList = pipeline.emit_by_name("get-audio-tags", &[&i]);
-            log::info!("audio stream {i}: {tags:?}");
-            self.audio_codes
-                .push(if let Some(title) = tags.get::<gst::tags::Title>() {
-                    title.get().to_string()
-                } else if let Some(language_code) = tags.get::<gst::tags::LanguageCode>() {
-                    let language_code = language_code.get();
-                    language_name(language_code).unwrap_or_else(|| language_code.to_string())
-                } else {
-                    format!("Audio #{i}")
-                });
+        let mime_type_opt = sample
+            .caps()
+            .and_then(|caps| caps.structure(0))
+            .map(|structure| structure.name().to_string());
+        if !is_font_attachment(&sample)
+            || mime_type_opt
+                .is_some_and(|mime_type| ASS_FONT_MIME_TYPES.contains(&mime_type.as_str()))
+        {
+            samples.push(sample);
+            continue;
         }
-        self.current_audio = pipeline.property::<i32>("current-audio");
 
-        let n_text = pipeline.property::<i32>("n-text");
-        self.text_codes = Vec::with_capacity(n_text as usize);
-        for i in 0..n_text {
-            let tags: gst::TagList = pipeline.emit_by_name("get-text-tags", &[&i]);
-            log::info!("text stream {i}: {tags:?}");
-            self.text_codes
-                .push(if let Some(title) = tags.get::<gst::tags::Title>() {
-                    title.get().to_string()
-                } else if let Some(language_code) = tags.get::<gst::tags::LanguageCode>() {
-                    let language_code = language_code.get();
-                    language_name(language_code).unwrap_or_else(|| language_code.to_string())
-                } else {
-                    format!("Subtitle #{i}")
-                });
+        // libass detects the font format itself, so the exact type does not matter
+        let caps = gst::Caps::new_empty_simple(ASS_FONT_MIME_TYPES[0]);
+        let buffer_opt = sample.buffer_owned();
+        let mut builder = gst::Sample::builder().caps(&caps);
+        if let Some(buffer) = &buffer_opt {
+            builder = builder.buffer(buffer);
         }
-        self.current_text = pipeline.property::<i32>("current-text");
+        if let Some(info) = sample.info() {
+            builder = builder.info(info.to_owned());
+        }
+        samples.push(builder.build());
+        changed = true;
+    }
+    if !changed {
+        return None;
+    }
 
-        //TODO: Flags can be used to enable/disable subtitles
-        let flags_value = pipeline.property_value("flags");
-        println!("original flags {:?}", flags_value);
-        match flags_value.transform::<i32>() {
-            Ok(flags_transform) => match flags_transform.get::<i32>() {
-                Ok(mut flags) => {
-                    flags |= GST_PLAY_FLAG_VIDEO | GST_PLAY_FLAG_AUDIO | GST_PLAY_FLAG_TEXT;
-                    match gst::glib::Value::from(flags).transform_with_type(flags_value.type_()) {
-                        Ok(value) => pipeline.set_property("flags", value),
-                        Err(err) => {
-                            log::warn!("failed to transform int to flags: {err}");
-                        }
-                    }
-                }
-                Err(err) => {
-                    log::warn!("failed to get flags as int: {err}");
-                }
-            },
-            Err(err) => {
-                log::warn!("failed to transform flags to int: {err}");
+    log::info!(
+        "passing {} attachments to assrender as fonts",
+        samples.len()
+    );
+    let mut new_tags = tags.to_owned();
+    {
+        let new_tags = new_tags.make_mut();
+        new_tags.remove_generic("attachment");
+        for sample in samples {
+            if let Err(err) = new_tags.add_generic("attachment", sample, gst::TagMergeMode::Append)
+            {
+                log::warn!("failed to add attachment: {err}");
             }
         }
-        println!("updated flags {:?}", pipeline.property_value("flags"));
-
-        self.update_title()
     }
+    Some(new_tags)
+}
 
-    fn update_controls(&mut self, in_use: bool) {
-        if in_use {
-            self.controls = true;
-            self.controls_time = Instant::now();
-        } else if self.controls && self.controls_time.elapsed() > CONTROLS_TIMEOUT {
-            self.controls = false;
+/// Watches the sink pads of assrender, where font attachments arrive as tags. This is only
+/// done once per element, unlike [`element_setup`] which also runs when the config changes.
+fn watch_font_attachments(element: &gst::Element) {
+    if element
+        .factory()
+        .is_some_and(|factory| factory.name().as_str() == "assrender")
+    {
+        for pad in element.sink_pads() {
+            pad.add_probe(gst::PadProbeType::EVENT_DOWNSTREAM, font_attachments_probe);
         }
     }
+}
 
-    fn update_config(&mut self) -> Command<Message> {
-        cosmic::app::command::set_theme(self.flags.config.app_theme.theme())
+/// Makes sure font attachments in tag events are loaded by assrender
+fn font_attachments_probe(_pad: &gst::Pad, info: &mut gst::PadProbeInfo) -> gst::PadProbeReturn {
+    let tags_opt = match &info.data {
+        Some(gst::PadProbeData::Event(event)) => match event.view() {
+            gst::EventView::Tag(tag) => normalize_font_attachments(tag.tag()),
+            _ => None,
+        },
+        _ => None,
+    };
+    if let Some(tags) = tags_opt {
+        info.data = Some(gst::PadProbeData::Event(gst::event::Tag::new(tags)));
     }
+    gst::PadProbeReturn::Ok
+}
 
-    fn update_title(&mut self) -> Command<Message> {
-        //TODO: filename?
-        let title = "COSMIC Media Player";
-        self.set_window_title(title.to_string())
+/// Returns the first family of a Pango font description such as `"DejaVu Sans Bold 24"`
+fn font_family(font_desc: &str) -> Option<String> {
+    let family = font_desc.split(',').next()?;
+    let mut words: Vec<&str> = family.split_whitespace().collect();
+    while let Some(word) = words.last() {
+        let word = word.to_ascii_lowercase();
+        if word.trim_end_matches("px").parse::<f64>().is_ok()
+            || FONT_STYLE_WORDS.contains(&word.as_str())
+        {
+            words.pop();
+        } else {
+            break;
+        }
+    }
+    if words.is_empty() {
+        None
+    } else {
+        Some(words.join(" "))
     }
 }
 
-/// Implement [`cosmic::Application`] to integrate with COSMIC.
-impl Application for App {
-    /// Default async executor to use with the app.
-    type Executor = executor::Default;
-
-    /// Argument received [`cosmic::Application::new`].
-    type Flags = Flags;
+/// Returns the path of the fontconfig file used while playing
+fn subtitle_font_config_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("cosmic-player").join("subtitle-fonts.conf"))
+}
 
-    /// Message type specific to our [`App`].
-    type Message = Message;
+/// Writes a fontconfig file that includes the system configuration and prefers the fallback
+/// subtitle font for generic families, which is where libass falls back to. A relative
+/// include is looked up in fontconfig's own configuration directory, wherever it is installed.
+fn write_subtitle_font_config(path: &Path, config: &Config) -> io::Result<()> {
+    let mut contents = String::from(concat!(
+        "<?xml version=\"1.0\"?>\n",
+        "<!DOCTYPE fontconfig SYSTEM \"urn:fontconfig:fonts.dtd\">\n",
+        "<fontconfig>\n",
+        "  <include ignore_missing=\"yes\">fonts.conf</include>\n",
+    ));
+    if let Some(family) = config.subtitle_font.as_deref().and_then(font_family) {
+        let family = family
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        for generic in ["sans-serif", "serif", "monospace"] {
+            contents.push_str(&format!(
+                "  <alias binding=\"strong\">\n    <family>{generic}</family>\n    <prefer><family>{family}</family></prefer>\n  </alias>\n"
+            ));
+        }
+    }
+    contents.push_str("</fontconfig>\n");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)
+}
+
+/// Points fontconfig at the player's own file if a fallback subtitle font is configured, so
+/// libass can use it. This must run before other threads start, as it changes the environment.
+fn install_subtitle_font_config(config: &Config) {
+    if config.subtitle_font.is_none() {
+        return;
+    }
+    if env::var_os("FONTCONFIG_FILE").is_some() {
+        log::info!("FONTCONFIG_FILE is set, SSA/ASS subtitles will not use the fallback font");
+        return;
+    }
+    let Some(path) = subtitle_font_config_path() else {
+        return;
+    };
+    match write_subtitle_font_config(&path, config) {
+        Ok(()) => env::set_var("FONTCONFIG_FILE", &path),
+        Err(err) => log::warn!("failed to write {:?}: {}", path, err),
+    }
+}
+
+/// Updates the fontconfig file with the current fallback subtitle font, if it is in use.
+/// libass reads it again for every file.
+fn update_subtitle_font_config(config: &Config) {
+    let Some(path) = subtitle_font_config_path() else {
+        return;
+    };
+    if env::var_os("FONTCONFIG_FILE").as_deref() == Some(path.as_os_str()) {
+        if let Err(err) = write_subtitle_font_config(&path, config) {
+            log::warn!("failed to write {:?}: {}", path, err);
+        }
+    }
+}
+
+/// Returns true for the DASH, HLS and Smooth Streaming demuxers
+fn is_adaptive_demuxer(element: &gst::Element) -> bool {
+    element
+        .factory()
+        .is_some_and(|factory| ADAPTIVE_DEMUXERS.contains(&factory.name().as_str()))
+}
+
+/// Sets the connection speed of a running adaptive demuxer. Demuxers declare the property
+/// with different integer types, so the value is converted and clamped to the declared one.
+fn set_connection_speed(element: &gst::Element, kbps: u64) {
+    use gst::glib::{ParamSpecInt, ParamSpecInt64, ParamSpecUInt, ParamSpecUInt64};
+
+    let Some(pspec) = element.find_property("connection-speed") else {
+        return;
+    };
+    let kbps_i64 = i64::try_from(kbps).unwrap_or(i64::MAX);
+    let value = if let Some(pspec) = pspec.downcast_ref::<ParamSpecUInt>() {
+        let kbps = kbps.clamp(pspec.minimum().into(), pspec.maximum().into());
+        u32::try_from(kbps).unwrap_or(u32::MAX).to_value()
+    } else if let Some(pspec) = pspec.downcast_ref::<ParamSpecUInt64>() {
+        kbps.clamp(pspec.minimum(), pspec.maximum()).to_value()
+    } else if let Some(pspec) = pspec.downcast_ref::<ParamSpecInt>() {
+        let kbps = kbps_i64.clamp(pspec.minimum().into(), pspec.maximum().into());
+        i32::try_from(kbps).unwrap_or(i32::MAX).to_value()
+    } else if let Some(pspec) = pspec.downcast_ref::<ParamSpecInt64>() {
+        kbps_i64.clamp(pspec.minimum(), pspec.maximum()).to_value()
+    } else {
+        log::warn!(
+            "{} has a connection-speed property of unsupported type {}",
+            element.name(),
+            pspec.value_type()
+        );
+        return;
+    };
+    element.set_property_from_value("connection-speed", &value);
+}
+
+/// Copies the manifest an adaptive demuxer receives, so its variants can be listed
+fn capture_manifest(element: &gst::Element, manifest: &Arc<Mutex<Vec<u8>>>) {
+    let Some(pad) = element
+        .static_pad("sink")
+        .filter(|_| is_adaptive_demuxer(element))
+    else {
+        return;
+    };
+    let manifest = manifest.clone();
+    pad.add_probe(
+        gst::PadProbeType::BUFFER | gst::PadProbeType::BUFFER_LIST,
+        move |_pad, info| {
+            let buffers: Vec<&gst::BufferRef> = match &info.data {
+                Some(gst::PadProbeData::Buffer(buffer)) => vec![&**buffer],
+                Some(gst::PadProbeData::BufferList(list)) => list.iter().collect(),
+                _ => Vec::new(),
+            };
+            if let Ok(mut manifest) = manifest.lock() {
+                for buffer in buffers {
+                    let Ok(map) = buffer.map_readable() else {
+                        continue;
+                    };
+                    if manifest.len() + map.len() <= MAX_MANIFEST_SIZE {
+                        manifest.extend_from_slice(&map);
+                    }
+                }
+            }
+            gst::PadProbeReturn::Ok
+        },
+    );
+}
+
+/// Returns the dropdown name of an adaptive stream variant
+fn stream_variant_name(variant: &StreamVariant) -> String {
+    let kbps = variant.connection_speed();
+    match variant.height_opt {
+        Some(height) => fl!("quality-variant", height = height, kbps = kbps),
+        None => fl!("quality-bitrate", kbps = kbps),
+    }
+}
+
+/// Returns the title from the tags of the first video or audio stream
+fn media_title(pipeline: &gst::Pipeline) -> Option<String> {
+    ["get-video-tags", "get-audio-tags"]
+        .into_iter()
+        .filter_map(|signal| stream_tags(pipeline, signal, 0))
+        .find_map(|tags| {
+            tags.get::<gst::tags::Title>()
+                .map(|title| title.get().to_string())
+        })
+        .filter(|title| !title.trim().is_empty())
+}
+
+/// Returns a notice if the media uses a codec or container feature known to cause playback
+/// problems
+fn codec_notice(pipeline: &gst::Pipeline, url: &url::Url) -> Option<String> {
+    // The video pad of playbin has decoded caps, so look at what parsers and demuxers produce
+    let elements: Vec<gst::Element> = pipeline.iterate_recurse().into_iter().flatten().collect();
+    let codec_opt = elements.iter().find_map(|element| {
+        element
+            .src_pads()
+            .iter()
+            .filter_map(|pad| pad.current_caps())
+            .find_map(|caps| problematic_codec(&caps))
+    });
+    if let Some(codec) = codec_opt {
+        return Some(fl!("codec-notice", codec = codec));
+    }
+
+    let matroska = elements.iter().any(|element| {
+        element
+            .factory()
+            .is_some_and(|factory| factory.name().as_str() == "matroskademux")
+    });
+    let path = url.to_file_path().ok().filter(|_| matroska)?;
+    match fs::File::open(&path)
+        .and_then(|file| matroska::has_negative_timestamps(&mut io::BufReader::new(file)))
+    {
+        Ok(true) => Some(fl!("timestamps-notice")),
+        Ok(false) => None,
+        Err(err) => {
+            log::warn!("failed to read timestamps of {:?}: {}", path, err);
+            None
+        }
+    }
+}
+
+/// Returns a description of an encoded video format that is known to cause playback problems
+fn problematic_codec(caps: &gst::CapsRef) -> Option<String> {
+    let structure = caps.structure(0)?;
+    if structure.name().as_str() == "video/x-vp9" {
+        // High bit depth profiles are often not supported by decoders
+        let profile = structure.get::<&str>("profile").ok()?;
+        if profile == "2" || profile == "3" {
+            return Some(format!("VP9 profile {}", profile));
+        }
+    }
+    None
+}
+
+/// Parses a timestamp given as seconds (`90`) or as `MM:SS` or `HH:MM:SS` (`00:01:30`).
+/// Only the seconds may have a fraction, and minutes and seconds after a colon must be below 60.
+fn parse_time(time: &str) -> Option<Duration> {
+    let parts: Vec<&str> = time.split(':').map(|part| part.trim()).collect();
+    if parts.len() > 3 {
+        return None;
+    }
+    let mut secs = 0.0;
+    for (i, part) in parts.iter().enumerate() {
+        let last = i + 1 == parts.len();
+        let value = if last {
+            part.parse::<f64>().ok()?
+        } else {
+            part.parse::<u64>().ok()? as f64
+        };
+        if !value.is_finite() || value < 0.0 || (i > 0 && value >= 60.0) {
+            return None;
+        }
+        secs = secs * 60.0 + value;
+    }
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+/// Gets the tags of a playbin stream, logging and skipping tags that cannot be retrieved
+fn stream_tags(pipeline: &gst::Pipeline, signal: &str, index: i32) -> Option<gst::TagList> {
+    let value = pipeline.emit_by_name_with_values(signal, &[index.to_value()])?;
+    tags_from_value(&value, signal, index)
+}
+
+/// Converts the value returned by a playbin tags signal, logging and skipping values that are
+/// not tag lists
+fn tags_from_value(value: &gst::glib::Value, signal: &str, index: i32) -> Option<gst::TagList> {
+    match value.get::<Option<gst::TagList>>() {
+        Ok(tags_opt) => tags_opt,
+        Err(err) => {
+            log::warn!("failed to get tags of stream {index} from {signal}: {err}");
+            None
+        }
+    }
+}
+
+/// Returns the dropdown name of a stream from its title or language, or the generic name
+fn stream_name(tags_opt: Option<&gst::TagList>, generic_name: String) -> String {
+    let Some(tags) = tags_opt else {
+        return generic_name;
+    };
+    if let Some(title) = tags.get::<gst::tags::Title>() {
+        return title.get().to_string();
+    }
+    if let Some(language_code) = tags.get::<gst::tags::LanguageCode>() {
+        let language_code = language_code.get();
+        return language_name(language_code).unwrap_or_else(|| language_code.to_string());
+    }
+    generic_name
+}
+
+fn format_time(time_float: f64) -> String {
+    let time = time_float.floor() as i64;
+    let seconds = time % 60;
+    let minutes = (time / 60) % 60;
+    let hours = (time / 60) / 60;
+    format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+}
+
+/// Creates the audio filter applied by playbin, if any is needed
+fn audio_filter(config: &Config, track_gain: f64) -> Option<gst::Element> {
+    let mut descriptions = Vec::with_capacity(4);
+    if config.normalize_volume {
+        descriptions.push("rgvolume ! rglimiter");
+    }
+    if config.equalizer.iter().any(|&gain| gain != 0.0) {
+        descriptions.push("equalizer-10bands name=equalizer");
+    }
+    if config.max_volume > 1.0 {
+        // Boost in floating point and limit the result to prevent clipping
+        descriptions.push("audioconvert ! volume name=boost ! rglimiter");
+    }
+    if track_gain != 0.0 {
+        descriptions.push("audioconvert ! volume name=track_gain ! rglimiter");
+    }
+    if descriptions.is_empty() {
+        return None;
+    }
+    match gst::parse::bin_from_description(&descriptions.join(" ! "), true) {
+        Ok(bin) => {
+            if let Some(equalizer) = bin.by_name("equalizer") {
+                update_equalizer(config, &equalizer);
+            }
+            if let Some(element) = bin.by_name("track_gain") {
+                element.set_property("volume", db_to_linear(track_gain));
+            }
+            Some(bin.upcast())
+        }
+        Err(err) => {
+            log::warn!("failed to create audio filter: {err}");
+            None
+        }
+    }
+}
+
+fn db_to_linear(gain: f64) -> f64 {
+    10f64.powf(gain / 20.0)
+}
+
+fn update_equalizer(config: &Config, equalizer: &gst::Element) {
+    for (band, gain) in config.equalizer.iter().enumerate() {
+        equalizer.set_property(&format!("band{}", band), gain);
+    }
+}
+
+/// Applies the configured color balance using the playbin color balance interface
+fn update_color_balance(config: &Config, pipeline: &gst::Pipeline) {
+    use gstreamer_video::prelude::ColorBalanceExt;
+
+    let Some(color_balance) = pipeline.dynamic_cast_ref::<gstreamer_video::ColorBalance>() else {
+        log::warn!("pipeline does not support color balance");
+        return;
+    };
+    for channel in color_balance.list_channels() {
+        let label = channel.label();
+        let Some(index) = COLOR_BALANCE_CHANNELS
+            .iter()
+            .position(|name| *name == label.as_str())
+        else {
+            continue;
+        };
+        // Map -1..1 onto the channel range, with zero at its center
+        let min = f64::from(channel.min_value());
+        let max = f64::from(channel.max_value());
+        let value = config.color_balance[index].clamp(-1.0, 1.0);
+        let center = (min + max) / 2.0;
+        color_balance.set_value(&channel, (center + value * (max - center)).round() as i32);
+    }
+}
+
+/// Describes the current streams for the statistics overlay
+fn playback_stats(pipeline: &gst::Pipeline) -> Vec<String> {
+    let mut stats = Vec::new();
+
+    let current_video = pipeline.property::<i32>("current-video").max(0);
+    let video_pad = pipeline.emit_by_name::<Option<gst::Pad>>("get-video-pad", &[&current_video]);
+    let video_caps = video_pad.and_then(|pad| pad.current_caps());
+    if let Some(structure) = video_caps.as_ref().and_then(|caps| caps.structure(0)) {
+        if let (Ok(width), Ok(height)) = (
+            structure.get::<i32>("width"),
+            structure.get::<i32>("height"),
+        ) {
+            stats.push(fl!("stats-resolution", width = width, height = height));
+        }
+        if let Ok(framerate) = structure.get::<gst::Fraction>("framerate") {
+            if framerate.denom() != 0 {
+                let fps = f64::from(framerate.numer()) / f64::from(framerate.denom());
+                stats.push(fl!("stats-framerate", fps = format!("{:.2}", fps)));
+            }
+        }
+    }
+    if let Some(tags) = stream_tags(pipeline, "get-video-tags", current_video) {
+        if let Some(codec) = tags.get::<gst::tags::VideoCodec>() {
+            stats.push(fl!("stats-video-codec", codec = codec.get()));
+        }
+        if let Some(bitrate) = tags.get::<gst::tags::Bitrate>() {
+            stats.push(fl!("stats-bitrate", kbps = bitrate.get() / 1000));
+        }
+    }
+
+    let current_audio = pipeline.property::<i32>("current-audio").max(0);
+    let audio_pad = pipeline.emit_by_name::<Option<gst::Pad>>("get-audio-pad", &[&current_audio]);
+    let audio_caps = audio_pad.and_then(|pad| pad.current_caps());
+    if let Some(structure) = audio_caps.as_ref().and_then(|caps| caps.structure(0)) {
+        if let Ok(rate) = structure.get::<i32>("rate") {
+            stats.push(fl!("stats-sample-rate", rate = rate));
+        }
+    }
+    if let Some(tags) = stream_tags(pipeline, "get-audio-tags", current_audio) {
+        if let Some(codec) = tags.get::<gst::tags::AudioCodec>() {
+            stats.push(fl!("stats-audio-codec", codec = codec.get()));
+        }
+    }
+
+    // Frame counts are kept by the sink
+    if let Some(video_sink) = pipeline.by_name("iced_video") {
+        let sink_stats = video_sink.property::<gst::Structure>("stats");
+        if let (Ok(rendered), Ok(dropped)) = (
+            sink_stats.get::<u64>("rendered"),
+            sink_stats.get::<u64>("dropped"),
+        ) {
+            stats.push(fl!("stats-frames", rendered = rendered, dropped = dropped));
+        }
+    }
+
+    stats
+}
+
+/// Collects media files in a directory, descending up to `depth` levels of subdirectories
+fn media_files(dir: &Path, config: &Config, depth: u32, paths: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(ok) => ok,
+        Err(err) => {
+            log::warn!("failed to read directory {:?}: {}", dir, err);
+            return;
+        }
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        {
+            continue;
+        }
+        if path.is_dir() {
+            if depth > 0 {
+                media_files(&path, config, depth - 1, paths);
+            }
+        } else if config.show_all_files
+            || path.extension().is_some_and(|extension| {
+                let extension = extension.to_string_lossy().to_lowercase();
+                config
+                    .media_extensions
+                    .iter()
+                    .any(|media| *media == extension)
+            })
+        {
+            paths.push(path);
+        }
+    }
+}
+
+/// Finds a subtitle file next to a local video with the same base name
+fn sidecar_subtitle(url: &url::Url) -> Option<url::Url> {
+    let path = url.to_file_path().ok()?;
+    SUBTITLE_EXTENSIONS.iter().find_map(|extension| {
+        // Only the extension is replaced, so dotted names like Show.S01E01.mkv keep every part
+        let subtitle_path = path.with_extension(extension);
+        if subtitle_path.is_file() {
+            url::Url::from_file_path(&subtitle_path).ok()
+        } else {
+            None
+        }
+    })
+}
+
+/// Shows a desktop notification that playback finished, returning true if replay was chosen
+#[cfg(feature = "xdg-portal")]
+async fn notify_finished(name: String) -> ashpd::Result<bool> {
+    use ashpd::desktop::notification::{Button, Notification, NotificationProxy};
+    use cosmic::iced::futures::StreamExt;
+    use std::sync::atomic::AtomicU64;
+
+    // A newer notification replaces this one, so only the latest listens for actions
+    static SERIAL: AtomicU64 = AtomicU64::new(0);
+    let serial = SERIAL.fetch_add(1, Ordering::SeqCst) + 1;
+
+    let proxy = NotificationProxy::new().await?;
+    let mut actions = proxy.receive_action_invoked().await?;
+    proxy
+        .add_notification(
+            FINISHED_NOTIFICATION_ID,
+            Notification::new(&fl!("playback-finished"))
+                .body(name.as_str())
+                .default_action(None)
+                .button(Button::new(&fl!("replay"), "replay")),
+        )
+        .await?;
+    let action_opt = tokio::time::timeout(NOTIFICATION_TIMEOUT, async {
+        while let Some(action) = actions.next().await {
+            if SERIAL.load(Ordering::SeqCst) != serial {
+                return None;
+            }
+            if action.id() == FINISHED_NOTIFICATION_ID {
+                return Some(action.name() == "replay");
+            }
+        }
+        None
+    })
+    .await;
+    match action_opt {
+        Ok(Some(replay)) => Ok(replay),
+        // Replaced by a newer notification
+        Ok(None) => Ok(false),
+        // The portal does not report dismissed notifications, so withdraw it once it is stale
+        Err(_) => {
+            if SERIAL.load(Ordering::SeqCst) == serial {
+                proxy.remove_notification(FINISHED_NOTIFICATION_ID).await?;
+            }
+            Ok(false)
+        }
+    }
+}
+
+/// Returns the available audio output devices
+fn audio_devices() -> Vec<gst::Device> {
+    let monitor = gst::DeviceMonitor::new();
+    monitor.add_filter(Some("Audio/Sink"), None);
+    if let Err(err) = monitor.start() {
+        log::warn!("failed to start device monitor: {err}");
+        return Vec::new();
+    }
+    let devices = monitor.devices().into_iter().collect();
+    monitor.stop();
+    devices
+}
+
+/// Creates a sink for the named audio output device
+fn audio_sink(name: &str) -> Option<gst::Element> {
+    let Some(device) = audio_devices()
+        .into_iter()
+        .find(|device| device.display_name().as_str() == name)
+    else {
+        log::warn!("audio device {:?} not found, using default", name);
+        return None;
+    };
+    match device.create_element(None) {
+        Ok(element) => Some(element),
+        Err(err) => {
+            log::warn!("failed to create sink for audio device {:?}: {err}", name);
+            None
+        }
+    }
+}
+
+/// Returns a short name for the media at the URL, used for generated file names
+fn url_name(url: &url::Url) -> String {
+    let name_opt = match url.to_file_path() {
+        Ok(path) => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned()),
+        Err(()) => url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| segment.to_string()),
+    };
+    name_opt
+        .or_else(|| url.host_str().map(|host| host.to_string()))
+        .unwrap_or_else(|| "media".to_string())
+}
+
+/// Converts the last frame shown by the video sink to an RGBA image
+fn frame_image(pipeline: &gst::Pipeline) -> Result<image::RgbaImage, Box<dyn std::error::Error>> {
+    let video_sink = pipeline
+        .by_name("iced_video")
+        .ok_or("failed to find video sink")?;
+    let sample = video_sink
+        .property::<Option<gst::Sample>>("last-sample")
+        .ok_or("no frame available")?;
+    let caps = sample.caps().ok_or("frame has no caps")?;
+    let buffer = sample.buffer().ok_or("frame has no buffer")?;
+    let info = gstreamer_video::VideoInfo::from_caps(caps)?;
+    let frame = gstreamer_video::VideoFrameRef::from_buffer_ref_readable(buffer, &info)?;
+    if frame.format() != gstreamer_video::VideoFormat::Nv12 {
+        return Err(format!("unsupported frame format {:?}", frame.format()).into());
+    }
+
+    // Convert NV12 using BT.601 coefficients
+    let y_plane = frame.plane_data(0)?;
+    let uv_plane = frame.plane_data(1)?;
+    let y_stride = frame.plane_stride()[0] as usize;
+    let uv_stride = frame.plane_stride()[1] as usize;
+    Ok(image::RgbaImage::from_fn(
+        frame.width(),
+        frame.height(),
+        |x, y| {
+            let (x, y) = (x as usize, y as usize);
+            let c = f32::from(y_plane[y * y_stride + x]) - 16.0;
+            let uv = (y / 2) * uv_stride + (x / 2) * 2;
+            let d = f32::from(uv_plane[uv]) - 128.0;
+            let e = f32::from(uv_plane[uv + 1]) - 128.0;
+            let r = 1.164 * c + 1.596 * e;
+            let g = 1.164 * c - 0.392 * d - 0.813 * e;
+            let b = 1.164 * c + 2.017 * d;
+            image::Rgba([
+                r.clamp(0.0, 255.0) as u8,
+                g.clamp(0.0, 255.0) as u8,
+                b.clamp(0.0, 255.0) as u8,
+                255,
+            ])
+        },
+    ))
+}
+
+/// Encodes the last frame shown by the video sink as PNG data
+fn frame_png(pipeline: &gst::Pipeline) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let image = frame_image(pipeline)?;
+    let mut data = Vec::new();
+    image.write_to(&mut io::Cursor::new(&mut data), image::ImageFormat::Png)?;
+    Ok(data)
+}
+
+/// Replaces characters that are not allowed in file names on common file systems
+fn sanitize_file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .take(100)
+        .collect();
+    // Leading dots would hide the file
+    let name = name.trim().trim_start_matches('.').trim_end_matches('.');
+    if name.is_empty() {
+        "screenshot".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Saves the last frame shown by the video sink as a PNG in the pictures directory
+fn screenshot(
+    pipeline: &gst::Pipeline,
+    name: &str,
+    position: f64,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let image = frame_image(pipeline)?;
+    let dir = dirs::picture_dir()
+        .or_else(dirs::home_dir)
+        .ok_or("failed to find pictures directory")?;
+    let path = dir.join(format!(
+        "{}_{}.png",
+        sanitize_file_name(name),
+        format_time(position).replace(':', "-")
+    ));
+    image.save(&path)?;
+    Ok(path)
+}
+
+/// Parses a command line argument as a URL or a path to a file
+fn url_from_arg(arg: &str) -> Option<url::Url> {
+    match url::Url::parse(arg) {
+        Ok(url) => Some(url),
+        Err(_) => match fs::canonicalize(arg) {
+            Ok(path) => match url::Url::from_file_path(&path) {
+                Ok(url) => Some(url),
+                Err(()) => {
+                    log::warn!("failed to parse argument {:?}", arg);
+                    None
+                }
+            },
+            Err(_) => {
+                log::warn!("failed to parse argument {:?}", arg);
+                None
+            }
+        },
+    }
+}
+
+/// Prints media information as JSON for `--probe`, returning the exit code
+fn probe_main(arg_opt: Option<String>) -> i32 {
+    let Some(url) = arg_opt.as_deref().and_then(url_from_arg) else {
+        eprintln!("--probe requires a file or URL");
+        return 1;
+    };
+    let info = match probe::probe(&url) {
+        Ok(ok) => ok,
+        Err(err) => {
+            eprintln!("failed to probe {}: {}", url, err);
+            return 1;
+        }
+    };
+    match serde_json::to_string_pretty(&info) {
+        Ok(json) => {
+            println!("{}", json);
+            0
+        }
+        Err(err) => {
+            eprintln!("failed to serialize media info: {}", err);
+            1
+        }
+    }
+}
+
+/// Computes the OpenSubtitles hash: the file size plus the 64-bit little endian words of the
+/// first and last 64 KiB, with wrapping addition
+fn opensubtitles_hash(path: &Path) -> io::Result<u64> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    const CHUNK_SIZE: u64 = 64 * 1024;
+
+    let mut file = fs::File::open(path)?;
+    let size = file.metadata()?.len();
+    if size < CHUNK_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "file too small to hash",
+        ));
+    }
+
+    let mut hash = size;
+    let mut chunk = vec![0; CHUNK_SIZE as usize];
+    for offset in [0, size - CHUNK_SIZE] {
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut chunk)?;
+        for word in chunk.chunks_exact(8) {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(word);
+            hash = hash.wrapping_add(u64::from_le_bytes(bytes));
+        }
+    }
+    Ok(hash)
+}
+
+/// Runs the configured subtitle command and returns the subtitle path it printed
+fn run_subtitle_command(command: &str, url: &url::Url) -> Result<PathBuf, String> {
+    use std::io::Read;
+
+    let (media, hash) = match url.to_file_path() {
+        Ok(path) => {
+            let hash = match opensubtitles_hash(&path) {
+                Ok(hash) => format!("{:016x}", hash),
+                Err(err) => {
+                    log::warn!("failed to hash {:?}: {}", path, err);
+                    String::new()
+                }
+            };
+            (path.to_string_lossy().into_owned(), hash)
+        }
+        Err(()) => (url.to_string(), String::new()),
+    };
+
+    let mut child = process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", command))
+        .arg("sh")
+        .arg(&media)
+        .arg(&hash)
+        .stdin(process::Stdio::null())
+        .stdout(process::Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("failed to run {:?}: {}", command, err))?;
+
+    // Read the output while waiting so that a command printing more than the pipe buffer
+    // does not block forever
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| format!("failed to read output of {:?}", command))?;
+    let (output_tx, output_rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = output_tx.send(stdout.read_to_end(&mut output).map(|_| output));
+    });
+
+    let start = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if start.elapsed() > SUBTITLE_COMMAND_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("{:?} timed out", command));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(100)),
+            Err(err) => return Err(format!("failed to wait for {:?}: {}", command, err)),
+        }
+    };
+    if !status.success() {
+        return Err(format!("{:?} failed with {}", command, status));
+    }
+    // Background processes left by the command may keep the output open
+    let output = output_rx
+        .recv_timeout(SUBTITLE_COMMAND_TIMEOUT.saturating_sub(start.elapsed()))
+        .map_err(|_| format!("{:?} timed out", command))?
+        .map_err(|err| format!("failed to read output of {:?}: {}", command, err))?;
+
+    let stdout = String::from_utf8_lossy(&output);
+    match stdout.lines().map(str::trim).find(|line| !line.is_empty()) {
+        Some(line) => Ok(PathBuf::from(line)),
+        None => Err(format!("{:?} did not print a subtitle path", command)),
+    }
+}
+
+/// Opens the folder containing a local file in the default file manager
+fn open_containing_folder(url: &url::Url) {
+    let Some(dir) = url
+        .to_file_path()
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+    else {
+        log::warn!("{} is not a local file", url);
+        return;
+    };
+    if let Err(err) = process::Command::new("xdg-open").arg(&dir).spawn() {
+        log::error!("failed to open {:?}: {}", dir, err);
+    }
+}
+
+/// Opens the URL in a separate player process
+fn spawn_window(url: &url::Url) {
+    match std::env::current_exe() {
+        Ok(exe) => {
+            if let Err(err) = process::Command::new(&exe).arg(url.as_str()).spawn() {
+                log::error!("failed to spawn {:?}: {}", exe, err);
+            }
+        }
+        Err(err) => {
+            log::error!("failed to get current executable: {}", err);
+        }
+    }
+}
+
+/// Runs application with these settings
+#[rustfmt::skip]
+pub fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    localize::localize();
+
+    // Probing exits before any config is loaded or window is opened
+    let mut args = std::env::args().skip(1);
+    if args.any(|arg| arg == "--probe") {
+        process::exit(probe_main(args.next()));
+    }
+
+    let (config_handler, config) = match cosmic_config::Config::new(App::APP_ID, CONFIG_VERSION) {
+        Ok(config_handler) => {
+            let config = match Config::get_entry(&config_handler) {
+                Ok(ok) => ok,
+                Err((errs, config)) => {
+                    log::info!("errors loading config: {:?}", errs);
+                    config
+                }
+            };
+            (Some(config_handler), config)
+        }
+        Err(err) => {
+            log::error!("failed to create config handler: {}", err);
+            (None, Config::default())
+        }
+    };
+
+    let (state_handler, state) =
+        match cosmic_config::Config::new_state(App::APP_ID, CONFIG_STATE_VERSION) {
+            Ok(state_handler) => {
+                let state = match ConfigState::get_entry(&state_handler) {
+                    Ok(ok) => ok,
+                    Err((errs, state)) => {
+                        log::info!("errors loading config state: {:?}", errs);
+                        state
+                    }
+                };
+                (Some(state_handler), state)
+            }
+            Err(err) => {
+                log::error!("failed to create config state handler: {}", err);
+                (None, ConfigState::default())
+            }
+        };
+
+    install_subtitle_font_config(&config);
+
+    let mut settings = Settings::default();
+    settings = settings.theme(config.app_theme.theme());
+    settings = settings.size_limits(Limits::NONE.min_width(360.0).min_height(180.0));
+    settings = settings.size(Size::new(config.window_width as f32, config.window_height as f32));
+
+    let mut fullscreen = false;
+    let mut start_opt = None;
+    let mut audio_track_opt = None;
+    let mut text_track_opt = None;
+    let mut url_arg_opt = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--fullscreen" || arg == "-f" {
+            fullscreen = true;
+        } else if arg == "--start" {
+            match args.next().as_deref().map(|time| (time, parse_time(time))) {
+                Some((_, Some(start))) => start_opt = Some(start),
+                Some((time, None)) => log::warn!("failed to parse start time {:?}", time),
+                None => log::warn!("missing value for --start"),
+            }
+        } else if arg == "--audio-track" {
+            match args.next() {
+                Some(track) => audio_track_opt = Some(TrackSelection::parse(&track)),
+                None => log::warn!("missing value for --audio-track"),
+            }
+        } else if arg == "--sub-track" {
+            match args.next() {
+                Some(track) => text_track_opt = Some(TrackSelection::parse(&track)),
+                None => log::warn!("missing value for --sub-track"),
+            }
+        } else if url_arg_opt.is_none() {
+            url_arg_opt = Some(arg);
+        }
+    }
+
+    let url_opt = url_arg_opt.as_deref().and_then(url_from_arg);
+
+    let flags = Flags {
+        config_handler,
+        config,
+        state_handler,
+        state,
+        url_opt,
+        start_opt,
+        audio_track_opt,
+        text_track_opt,
+        fullscreen,
+    };
+    cosmic::app::run::<App>(settings, flags)?;
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Action {
+    AudioDelayDecrease,
+    AudioDelayIncrease,
+    ClearLoopPoints,
+    ColorBalance,
+    CopyFrame,
+    CopyTimestamp,
+    CycleAspect,
+    DeinterlaceAuto,
+    DeinterlaceOff,
+    DeinterlaceOn,
+    FileClose,
+    FileOpen,
+    FileOpenRecent(usize),
+    Fullscreen,
+    MiniPlayer,
+    Mute,
+    OpenContainingFolder,
+    OpenUrl,
+    Reload,
+    RequestSubtitles,
+    PlayPause,
+    Screenshot,
+    SeekBackward,
+    SeekBackwardCoarse,
+    SeekBackwardFine,
+    SeekForward,
+    SeekForwardCoarse,
+    SeekForwardFine,
+    SetLoopPointA,
+    SetLoopPointB,
+    Settings,
+    SleepTimer15,
+    SleepTimer30,
+    SleepTimer60,
+    SleepTimerEndOfFile,
+    SleepTimerOff,
+    Stop,
+    SubtitleDelayDecrease,
+    SubtitleDelayIncrease,
+    SubtitleOpen,
+    ToggleLoop,
+    ToggleStats,
+    VolumeDown,
+    VolumeUp,
+    WindowClose,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+}
+
+impl MenuAction for Action {
+    type Message = Message;
+
+    fn message(&self) -> Message {
+        match self {
+            Self::AudioDelayDecrease => Message::AudioDelay(-50),
+            Self::AudioDelayIncrease => Message::AudioDelay(50),
+            Self::ClearLoopPoints => Message::ClearLoopPoints,
+            Self::ColorBalance => Message::DropdownToggle(DropdownKind::ColorBalance),
+            Self::CopyFrame => Message::CopyFrame,
+            Self::CopyTimestamp => Message::CopyTimestamp,
+            Self::CycleAspect => Message::CycleAspect,
+            Self::DeinterlaceAuto => Message::Deinterlace(Deinterlace::Auto),
+            Self::DeinterlaceOff => Message::Deinterlace(Deinterlace::Off),
+            Self::DeinterlaceOn => Message::Deinterlace(Deinterlace::On),
+            Self::FileClose => Message::FileClose,
+            Self::FileOpen => Message::FileOpen,
+            Self::FileOpenRecent(index) => Message::FileOpenRecent(index),
+            Self::Fullscreen => Message::Fullscreen,
+            Self::MiniPlayer => Message::MiniPlayer,
+            Self::Mute => Message::AudioToggle,
+            Self::OpenContainingFolder => Message::OpenContainingFolder,
+            Self::OpenUrl => Message::OpenUrlDialog,
+            Self::Reload => Message::FileReload,
+            Self::RequestSubtitles => Message::RequestSubtitles,
+            Self::PlayPause => Message::PlayPause,
+            Self::Screenshot => Message::Screenshot,
+            Self::SeekBackward => Message::SeekRelative(-10.0),
+            Self::SeekBackwardCoarse => Message::SeekCoarse(-1.0),
+            Self::SeekBackwardFine => Message::SeekFine(-1.0),
+            Self::SeekForward => Message::SeekRelative(10.0),
+            Self::SeekForwardCoarse => Message::SeekCoarse(1.0),
+            Self::SeekForwardFine => Message::SeekFine(1.0),
+            Self::SetLoopPointA => Message::SetLoopPointA,
+            Self::SetLoopPointB => Message::SetLoopPointB,
+            Self::Settings => Message::ToggleContextPage(ContextPage::Settings),
+            Self::SleepTimer15 => Message::SetSleepTimer(Some(Duration::from_secs(15 * 60))),
+            Self::SleepTimer30 => Message::SetSleepTimer(Some(Duration::from_secs(30 * 60))),
+            Self::SleepTimer60 => Message::SetSleepTimer(Some(Duration::from_secs(60 * 60))),
+            Self::SleepTimerEndOfFile => Message::SleepAtEndOfFile,
+            Self::SleepTimerOff => Message::SetSleepTimer(None),
+            Self::Stop => Message::Stop,
+            Self::SubtitleDelayDecrease => Message::SubtitleDelay(-100),
+            Self::SubtitleDelayIncrease => Message::SubtitleDelay(100),
+            Self::SubtitleOpen => Message::SubtitleOpen,
+            Self::ToggleLoop => Message::ToggleLoop,
+            Self::ToggleStats => Message::ToggleStats,
+            Self::VolumeDown => Message::AudioVolumeStep(-0.05),
+            Self::VolumeUp => Message::AudioVolumeStep(0.05),
+            Self::WindowClose => Message::WindowClose,
+            Self::ZoomIn => Message::Zoom(1.25),
+            Self::ZoomOut => Message::Zoom(0.8),
+            Self::ZoomReset => Message::ZoomReset,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Flags {
+    config_handler: Option<cosmic_config::Config>,
+    config: Config,
+    state_handler: Option<cosmic_config::Config>,
+    state: ConfigState,
+    url_opt: Option<url::Url>,
+    /// Position to seek to when the first file is loaded
+    start_opt: Option<Duration>,
+    /// Audio stream to select when the first file is loaded
+    audio_track_opt: Option<TrackSelection>,
+    /// Subtitle stream to select when the first file is loaded
+    text_track_opt: Option<TrackSelection>,
+    /// Start in fullscreen mode
+    fullscreen: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AspectMode {
+    Fit,
+    Fill,
+    Stretch,
+    Original,
+}
+
+impl AspectMode {
+    fn content_fit(self) -> ContentFit {
+        match self {
+            Self::Fit => ContentFit::Contain,
+            Self::Fill => ContentFit::Cover,
+            Self::Stretch => ContentFit::Fill,
+            Self::Original => ContentFit::None,
+        }
+    }
+
+    fn name(self) -> String {
+        match self {
+            Self::Fit => fl!("aspect-fit"),
+            Self::Fill => fl!("aspect-fill"),
+            Self::Stretch => fl!("aspect-stretch"),
+            Self::Original => fl!("aspect-original"),
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Self::Fit => Self::Fill,
+            Self::Fill => Self::Stretch,
+            Self::Stretch => Self::Original,
+            Self::Original => Self::Fit,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ContextPage {
+    Settings,
+}
+
+impl ContextPage {
+    fn title(&self) -> String {
+        match self {
+            Self::Settings => fl!("settings"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DropdownKind {
+    Audio,
+    ColorBalance,
+    Equalizer,
+    Subtitle,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SleepTimer {
+    At(Instant),
+    EndOfFile,
+}
+
+/// Audio or subtitle stream chosen on the command line
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TrackSelection {
+    Index(usize),
+    Language(String),
+    Off,
+}
+
+impl TrackSelection {
+    fn parse(arg: &str) -> Self {
+        if arg == "off" {
+            Self::Off
+        } else if let Ok(index) = arg.parse() {
+            Self::Index(index)
+        } else {
+            Self::Language(arg.to_string())
+        }
+    }
+
+    /// Returns the stream index matching this selection, given the stream languages
+    fn index(&self, languages: &[Option<String>]) -> Option<usize> {
+        match self {
+            Self::Index(index) => (*index < languages.len()).then_some(*index),
+            Self::Language(language) => languages
+                .iter()
+                .position(|stream_language| stream_language.as_ref() == Some(language)),
+            Self::Off => None,
+        }
+    }
+}
+
+/// Playback state restored once a restarted pipeline has prerolled
+#[derive(Clone, Copy, Debug)]
+pub struct PipelineRestart {
+    /// Matches the `Message::PipelineRestarted` of the latest restart
+    id: u64,
+    position: Duration,
+    paused: bool,
+    volume: f64,
+    /// Select the last subtitle stream, which is the one just loaded
+    select_last_text: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct UrlDialog {
+    input: String,
+    error_opt: Option<String>,
+}
+
+/// PNG image data offered on the clipboard
+#[derive(Clone, Debug)]
+pub struct ClipboardImage(Vec<u8>);
+
+impl AsMimeTypes for ClipboardImage {
+    fn available(&self) -> Cow<'static, [String]> {
+        Cow::Owned(vec!["image/png".to_string()])
+    }
+
+    fn as_bytes(&self, mime_type: &str) -> Option<Cow<'static, [u8]>> {
+        if mime_type == "image/png" {
+            Some(Cow::Owned(self.0.clone()))
+        } else {
+            None
+        }
+    }
+}
+
+/// Messages that are used specifically by our [`App`].
+#[derive(Clone, Debug)]
+pub enum Message {
+    None,
+    AppTheme(AppTheme),
+    Config(Config),
+    CopyFrame,
+    CopyFrameReady(ClipboardImage),
+    CopyTimestamp,
+    CycleAspect,
+    Deinterlace(Deinterlace),
+    DropdownToggle(DropdownKind),
+    FileClose,
+    FileLoad(url::Url),
+    FileOpen,
+    FileOpenRecent(usize),
+    FileReload,
+    FolderLoad(PathBuf),
+    Fullscreen,
+    MiniPlayer,
+    HardwareDecoding(bool),
+    AutoRotate(bool),
+    ToneMapping(bool),
+    StyledSubtitles(bool),
+    Key(Modifiers, Key),
+    LoadSubtitle(PathBuf),
+    NoticeDismiss,
+    NormalizeVolume(bool),
+    NotifyOnFinish(bool),
+    OpenInNewWindow(bool),
+    PauseOnUnfocus(bool),
+    WindowFocused(bool),
+    OpenContainingFolder,
+    OpenUrlDialog,
+    Pan(f64, f64),
+    RequestSubtitles,
+    RequestSubtitlesFailed(String),
+    ScrollSeek(bool),
+    Scrolled(ScrollDelta),
+    ScrubPreview(bool),
+    UrlCancel,
+    UrlInput(String),
+    UrlSubmit,
+    AudioCode(usize),
+    AudioDelay(i64),
+    AudioDevice(usize),
+    AudioToggle,
+    AudioVolume(f64),
+    TrackGain(f64),
+    AudioVolumeStep(f64),
+    MaxVolume(f64),
+    ColorBalance(usize, f64),
+    ColorBalanceReset,
+    TextCode(usize),
+    PipelineRestarted(u64),
+    PlayFlag(i32, bool),
+    PlayPause,
+    Screenshot,
+    Seek(f64),
+    SeekRelative(f64),
+    /// Seek by the coarse step in the given direction
+    SeekCoarse(f64),
+    /// Seek by the fine step in the given direction
+    SeekFine(f64),
+    SeekHover(Option<f64>),
+    SeekRelease,
+    SetLoopPointA,
+    SetLoopPointB,
+    ClearLoopPoints,
+    SetSleepTimer(Option<Duration>),
+    SetStreamVariant(usize),
+    SleepAtEndOfFile,
+    Stop,
+    SubtitleDelay(i64),
+    SubtitleOpen,
+    SubtitlePosition(i32),
+    SubtitleSize(u32),
+    EndOfStream,
+    EqualizerBand(usize, f64),
+    ErrorDismiss,
+    LoadError(String),
+    MissingPlugin(gst::Message),
+    NewFrame,
+    PluginInstallFailed(String),
+    Reload,
+    ShowControls,
+    SystemThemeModeChange(cosmic_theme::ThemeMode),
+    Tick,
+    TitleProgress(bool),
+    ToggleContextPage(ContextPage),
+    ToggleLoop,
+    ToggleStats,
+    UpdateRate(u32),
+    ControlsHover(bool),
+    ControlsTimeout(u64),
+    WindowClose,
+    WindowResized(u32, u32),
+    WindowSave(u64, u32, u32),
+    Zoom(f64),
+    ZoomReset,
+}
+
+/// The [`App`] stores application-specific state.
+pub struct App {
+    core: Core,
+    flags: Flags,
+    app_themes: Vec<String>,
+    deinterlace_modes: Vec<String>,
+    update_rates: Vec<String>,
+    controls_timeouts: Vec<String>,
+    max_volumes: Vec<String>,
+    volume_boost: f64,
+    /// Zoom factor, one shows the whole frame
+    zoom: f64,
+    /// Center of the zoomed area, from -1 to 1 on each axis
+    pan: (f64, f64),
+    aspect_mode: AspectMode,
+    context_page: ContextPage,
+    controls: bool,
+    controls_hover: bool,
+    controls_time: Instant,
+    dropdown_opt: Option<DropdownKind>,
+    fullscreen: bool,
+    /// Compact always on top window without the header bar
+    mini_player: bool,
+    key_binds: HashMap<KeyBind, Action>,
+    video_opt: Option<Video>,
+    position: f64,
+    duration: f64,
+    duration_time: Instant,
+    dragging: bool,
+    /// Whether playback was paused when dragging started
+    paused_on_scrub: bool,
+    /// Playback was paused by the window losing focus, so it resumes on focus
+    paused_on_unfocus: bool,
+    /// Subtitles were turned off on the command line without changing the config
+    subtitles_forced_off: bool,
+    scrub_time: Instant,
+    loop_a: Option<f64>,
+    loop_b: Option<f64>,
+    audio_devices: Vec<String>,
+    audio_codes: Vec<String>,
+    audio_languages: Vec<Option<String>>,
+    current_audio: i32,
+    text_codes: Vec<String>,
+    text_languages: Vec<Option<String>>,
+    current_text: i32,
+    subtitle_delay: i64,
+    audio_delay: i64,
+    adaptive_streaming: bool,
+    /// Latest buffering percentage posted on the pipeline bus
+    buffering: Arc<AtomicI32>,
+    buffering_percent_opt: Option<i32>,
+    /// Resume playback once buffering completes
+    buffering_resume: bool,
+    network_stream: bool,
+    /// Title from the media tags, if it has one
+    media_title_opt: Option<String>,
+    /// Manifest received by the adaptive demuxer
+    stream_manifest: Arc<Mutex<Vec<u8>>>,
+    stream_variants: Vec<String>,
+    /// Connection speeds in kbps that select each variant, zero is automatic
+    stream_variant_speeds: Vec<u64>,
+    /// Connection speed limit in kbps chosen by the user, zero is automatic
+    connection_speed: u64,
+    notice_opt: Option<String>,
+    error_opt: Option<String>,
+    /// The error can be retried by reloading the file
+    error_retry: bool,
+    /// A missing plugin is being installed
+    plugin_install_pending: bool,
+    osd_opt: Option<(String, Instant)>,
+    stats_opt: Option<Vec<String>>,
+    position_save_time: Instant,
+    restart_opt: Option<PipelineRestart>,
+    restart_count: u64,
+    seek_hover_opt: Option<f64>,
+    sleep_timer_opt: Option<SleepTimer>,
+    title_percent_opt: Option<u32>,
+    url_dialog_opt: Option<UrlDialog>,
+    url_input_id: widget::Id,
+    window_resize_id: u64,
+}
+
+impl App {
+    fn close(&mut self) {
+        self.save_position();
+        //TODO: drop does not work well
+        if let Some(mut video) = self.video_opt.take() {
+            log::info!("pausing video");
+            video.set_paused(true);
+            log::info!("dropping video");
+            drop(video);
+            log::info!("dropped video");
+        }
+        self.position = 0.0;
+        self.duration = 0.0;
+        self.dragging = false;
+        self.restart_opt = None;
+        self.loop_a = None;
+        self.loop_b = None;
+        self.audio_codes = Vec::new();
+        self.audio_languages = Vec::new();
+        self.current_audio = -1;
+        self.text_codes = Vec::new();
+        self.text_languages = Vec::new();
+        self.current_text = -1;
+        self.subtitle_delay = 0;
+        self.audio_delay = 0;
+        self.volume_boost = 1.0;
+        self.zoom = 1.0;
+        self.pan = (0.0, 0.0);
+        // The controls are removed without an exit event
+        self.controls_hover = false;
+        self.paused_on_unfocus = false;
+        self.subtitles_forced_off = false;
+        self.notice_opt = None;
+        self.error_opt = None;
+        self.error_retry = false;
+        self.plugin_install_pending = false;
+        self.adaptive_streaming = false;
+        self.stream_variants = Vec::new();
+        self.stream_variant_speeds = Vec::new();
+        self.buffering_percent_opt = None;
+        self.buffering_resume = false;
+        self.network_stream = false;
+        self.media_title_opt = None;
+    }
+
+    /// Creates the playbin pipeline for a URL and wraps it in a [`Video`]
+    fn create_video(&mut self, url: &url::Url) -> Result<Video, iced_video_player::Error> {
+        //TODO: this code came from iced_video_player::Video::new and has been modified to stop the pipeline on error
+        //TODO: enable playback of files with only audio.
+        gst::init()?;
+
+        set_hardware_decoding(self.flags.config.hardware_decoding);
+        set_styled_subtitles(self.flags.config.styled_subtitles);
+        update_subtitle_font_config(&self.flags.config);
+
+        let pipeline = format!(
+            "playbin uri=\"{}\" video-sink=\"{}videoflip name=flip method={} ! videocrop name=zoom ! videoscale ! videoconvert ! appsink name=iced_video drop=true caps=video/x-raw,format=NV12,pixel-aspect-ratio=1/1\"",
+            url.as_str(),
+            tone_mapping_element(&self.flags.config),
+            flip_method(&self.flags.config)
+        );
+        let pipeline = gst::parse::launch(pipeline.as_ref())?
+            .downcast::<gst::Pipeline>()
+            .map_err(|_| iced_video_player::Error::Cast)?;
+
+        if let Some(audio_sink) = self
+            .flags
+            .config
+            .audio_device
+            .as_deref()
+            .and_then(audio_sink)
+        {
+            pipeline.set_property("audio-sink", &audio_sink);
+        }
+        if let Some(audio_filter) = audio_filter(&self.flags.config, self.track_gain()) {
+            pipeline.set_property("audio-filter", &audio_filter);
+        }
+        if self.connection_speed > 0 {
+            pipeline.set_property("connection-speed", self.connection_speed);
+        }
+        if let Some(font_desc) = subtitle_font_desc(&self.flags.config) {
+            pipeline.set_property("subtitle-font-desc", font_desc);
+        }
+        if let Some(subtitle_url) = sidecar_subtitle(url) {
+            log::info!("loading subtitles from {}", subtitle_url);
+            pipeline.set_property("suburi", subtitle_url.as_str());
+        }
+        let config = self.flags.config.clone();
+        self.stream_manifest = Arc::new(Mutex::new(Vec::new()));
+        let stream_manifest = self.stream_manifest.clone();
+        pipeline.connect("element-setup", false, move |args| {
+            match args[1].get::<gst::Element>() {
+                Ok(element) => {
+                    element_setup(&config, &element);
+                    watch_font_attachments(&element);
+                    capture_manifest(&element, &stream_manifest);
+                }
+                Err(err) => log::warn!("failed to get element for setup: {err}"),
+            }
+            None
+        });
+
+        // Watch buffering without taking messages from the bus used by the video player
+        self.buffering = Arc::new(AtomicI32::new(100));
+        if let Some(bus) = pipeline.bus() {
+            let buffering = self.buffering.clone();
+            bus.enable_sync_message_emission();
+            bus.connect_sync_message(Some("buffering"), move |_bus, message| {
+                if let gst::MessageView::Buffering(buffering_message) = message.view() {
+                    buffering.store(buffering_message.percent(), Ordering::Relaxed);
+                }
+            });
+        }
+
+        let video_sink: gst::Element = pipeline.property("video-sink");
+        let bin = video_sink
+            .pads()
+            .first()
+            .cloned()
+            .and_then(|pad| pad.dynamic_cast::<gst::GhostPad>().ok())
+            .and_then(|pad| pad.parent_element())
+            .and_then(|element| element.downcast::<gst::Bin>().ok())
+            .ok_or(iced_video_player::Error::Cast)?;
+        let video_sink = bin
+            .by_name("iced_video")
+            .and_then(|element| element.downcast::<gst_app::AppSink>().ok())
+            .ok_or(iced_video_player::Error::Cast)?;
+
+        Video::from_gst_pipeline(pipeline.clone(), video_sink, None).inspect_err(|_| {
+            if let Err(err) = pipeline.set_state(gst::State::Null) {
+                log::warn!("failed to stop pipeline: {err}");
+            }
+        })
+    }
+
+    fn load(&mut self) -> Command<Message> {
+        self.close();
+
+        let url = match &self.flags.url_opt {
+            Some(some) => some.clone(),
+            None => return Command::none(),
+        };
+
+        log::info!("Loading {}", url);
+
+        let mut video = match self.create_video(&url) {
+            Ok(ok) => ok,
+            Err(err) => {
+                log::warn!("failed to open {}: {err}", url);
+                return self.update(Message::LoadError(fl!(
+                    "load-error",
+                    url = url.as_str(),
+                    error = err.to_string()
+                )));
+            }
+        };
+
+        self.duration = video.duration().as_secs_f64();
+        self.duration_time = Instant::now();
+        video.set_looping(self.flags.config.looping);
+        if let Some(mut start) = self.flags.start_opt.take() {
+            if self.duration > 0.0 {
+                start = start.min(video.duration());
+            }
+            match video.seek(start, true) {
+                Ok(()) => self.position = start.as_secs_f64(),
+                Err(err) => log::warn!("failed to seek to start time {:?}: {err}", start),
+            }
+        } else if let Some(position) = self.flags.state.playback_position(&url) {
+            if position > RESUME_MARGIN && position < self.duration - RESUME_MARGIN {
+                let start = Duration::try_from_secs_f64(position).unwrap_or_default();
+                match video.seek(start, true) {
+                    Ok(()) => {
+                        self.position = position;
+                        self.show_osd(fl!("resumed", time = format_time(position)));
+                    }
+                    Err(err) => log::warn!("failed to resume at {}: {err}", position),
+                }
+            }
+        }
+        self.position_save_time = Instant::now();
+        let pipeline = video.pipeline();
+        self.video_opt = Some(video);
+
+        let n_audio = pipeline.property::<i32>("n-audio");
+        self.audio_codes = Vec::with_capacity(n_audio as usize);
+        self.audio_languages = Vec::with_capacity(n_audio as usize);
+        for i in 0..n_audio {
+            let tags_opt = stream_tags(&pipeline, "get-audio-tags", i);
+            log::info!("audio stream {i}: {tags_opt:?}");
+            self.audio_languages.push(
+                tags_opt
+                    .as_ref()
+                    .and_then(|tags| tags.get::<gst::tags::LanguageCode>())
+                    .map(|language_code| language_code.get().to_string()),
+            );
+            self.audio_codes
+                .push(stream_name(tags_opt.as_ref(), format!("Audio #{i}")));
+        }
+        if let Some(preferred) = &self.flags.config.preferred_audio_language {
+            if let Some(index) = self
+                .audio_languages
+                .iter()
+                .position(|language| language.as_ref() == Some(preferred))
+            {
+                log::info!("selecting preferred audio language {}", preferred);
+                pipeline.set_property("current-audio", index as i32);
+            }
+        }
+        if let Some(selection) = self.flags.audio_track_opt.take() {
+            match selection.index(&self.audio_languages) {
+                Some(index) => pipeline.set_property("current-audio", index as i32),
+                None => log::warn!("audio track {:?} not found", selection),
+            }
+        }
+        self.current_audio = pipeline.property::<i32>("current-audio");
+
+        self.update_text_codes(&pipeline);
+        if let Some(preferred) = &self.flags.config.preferred_text_language {
+            if let Some(index) = self
+                .text_languages
+                .iter()
+                .position(|language| language.as_ref() == Some(preferred))
+            {
+                log::info!("selecting preferred text language {}", preferred);
+                pipeline.set_property("current-text", index as i32);
+            }
+        }
+        if let Some(selection) = self.flags.text_track_opt.take() {
+            if selection == TrackSelection::Off {
+                self.subtitles_forced_off = true;
+            } else {
+                match selection.index(&self.text_languages) {
+                    Some(index) => pipeline.set_property("current-text", index as i32),
+                    None => log::warn!("subtitle track {:?} not found", selection),
+                }
+            }
+        }
+        self.current_text = pipeline.property::<i32>("current-text");
+
+        self.media_title_opt = media_title(&pipeline);
+        self.add_recent_file(url.clone(), self.media_title_opt.clone());
+
+        self.network_stream = url.scheme() != "file";
+        self.adaptive_streaming = pipeline
+            .iterate_recurse()
+            .into_iter()
+            .flatten()
+            .any(|element| is_adaptive_demuxer(&element));
+        if self.adaptive_streaming {
+            // The demuxer has the whole manifest once the stream is prerolled
+            let variants = match self.stream_manifest.lock() {
+                Ok(data) => manifest::variants(&String::from_utf8_lossy(&data)),
+                Err(_) => Vec::new(),
+            };
+            log::info!("adaptive stream variants: {:?}", variants);
+            self.stream_variant_speeds = std::iter::once(0)
+                .chain(variants.iter().map(StreamVariant::connection_speed))
+                .collect();
+            self.stream_variants = std::iter::once(fl!("quality-auto"))
+                .chain(variants.iter().map(stream_variant_name))
+                .collect();
+        }
+
+        self.update_flags();
+        update_color_balance(&self.flags.config, &pipeline);
+
+        if is_hdr(&pipeline) {
+            log::info!(
+                "HDR video detected, tone mapping {}",
+                if self.flags.config.tone_mapping {
+                    "enabled"
+                } else {
+                    "disabled"
+                }
+            );
+        }
+
+        self.notice_opt = codec_notice(&pipeline, &url);
+        if let Some(notice) = &self.notice_opt {
+            log::warn!("{}", notice);
+        }
+
+        self.update_title()
+    }
+
+    /// Changes properties that are only read when the pipeline starts. The pipeline prerolls
+    /// in the background and playback is restored by `Message::PipelineRestarted`.
+    fn restart_pipeline<F: FnOnce(&gst::Pipeline)>(&mut self, f: F) -> Command<Message> {
+        let volume = self.volume();
+        let Some(video) = &self.video_opt else {
+            return Command::none();
+        };
+        let pipeline = video.pipeline();
+        self.restart_count += 1;
+        let id = self.restart_count;
+        // Keep the state from before an earlier restart that has not finished
+        self.restart_opt = Some(match self.restart_opt {
+            Some(restart) => PipelineRestart { id, ..restart },
+            None => PipelineRestart {
+                id,
+                position: video.position(),
+                paused: video.paused(),
+                volume,
+                select_last_text: false,
+            },
+        });
+
+        if let Err(err) = pipeline.set_state(gst::State::Ready) {
+            log::warn!("failed to set pipeline to ready: {err}");
+        }
+        f(&pipeline);
+        if let Err(err) = pipeline.set_state(gst::State::Paused) {
+            log::warn!("failed to set pipeline to paused: {err}");
+        }
+        Command::perform(
+            async move {
+                let result = tokio::task::spawn_blocking(move || {
+                    pipeline.state(gst::ClockTime::from_seconds(5)).0
+                })
+                .await;
+                match result {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(err)) => log::warn!("failed to restart pipeline: {err}"),
+                    Err(err) => log::warn!("failed to wait for pipeline restart: {err}"),
+                }
+                message::app(Message::PipelineRestarted(id))
+            },
+            |x| x,
+        )
+    }
+
+    /// Lists the subtitle streams, the first entry turns subtitles off
+    fn update_text_codes(&mut self, pipeline: &gst::Pipeline) {
+        let n_text = pipeline.property::<i32>("n-text");
+        self.text_codes = Vec::with_capacity(n_text as usize + 1);
+        self.text_codes.push(fl!("subtitles-off"));
+        self.text_languages = Vec::with_capacity(n_text as usize);
+        for i in 0..n_text {
+            let tags_opt = stream_tags(pipeline, "get-text-tags", i);
+            log::info!("text stream {i}: {tags_opt:?}");
+            self.text_languages.push(
+                tags_opt
+                    .as_ref()
+                    .and_then(|tags| tags.get::<gst::tags::LanguageCode>())
+                    .map(|language_code| language_code.get().to_string()),
+            );
+            self.text_codes
+                .push(stream_name(tags_opt.as_ref(), format!("Subtitle #{i}")));
+        }
+    }
+
+    /// Applies the current config to elements already in the pipeline
+    fn update_elements(&self) {
+        let Some(video) = &self.video_opt else {
+            return;
+        };
+        for element in video.pipeline().iterate_recurse().into_iter().flatten() {
+            element_setup(&self.flags.config, &element);
+        }
+    }
+
+    /// Applies the subtitle font and size to the current file
+    fn update_subtitle_font(&self) {
+        if let Some(video) = &self.video_opt {
+            // An empty description restores the default font
+            let font_desc = subtitle_font_desc(&self.flags.config).unwrap_or_default();
+            video
+                .pipeline()
+                .set_property("subtitle-font-desc", font_desc);
+        }
+    }
+
+    /// Returns the gain in dB stored for the current file
+    fn track_gain(&self) -> f64 {
+        self.flags
+            .url_opt
+            .as_ref()
+            .and_then(|url| self.flags.state.track_gain(url))
+            .unwrap_or(0.0)
+    }
+
+    fn update_audio_filter(&mut self) -> Command<Message> {
+        // The audio filter can only be replaced when the pipeline is stopped
+        let audio_filter = audio_filter(&self.flags.config, self.track_gain());
+        self.restart_pipeline(|pipeline| {
+            pipeline.set_property("audio-filter", audio_filter.as_ref());
+        })
+    }
+
+    fn update_audio_devices(&mut self) {
+        self.audio_devices = vec![fl!("default-device")];
+        self.audio_devices.extend(
+            audio_devices()
+                .iter()
+                .map(|device| device.display_name().to_string()),
+        );
+    }
+
+    fn update_flags(&self) {
+        let Some(video) = &self.video_opt else {
+            return;
+        };
+        let pipeline = video.pipeline();
+        let config = &self.flags.config;
+
+        let flags_value = pipeline.property_value("flags");
+        println!("original flags {:?}", flags_value);
+        match flags_value.transform::<i32>() {
+            Ok(flags_transform) => match flags_transform.get::<i32>() {
+                Ok(mut flags) => {
+                    flags |= GST_PLAY_FLAG_VIDEO | GST_PLAY_FLAG_AUDIO;
+                    for (flag, enabled) in [
+                        (
+                            GST_PLAY_FLAG_TEXT,
+                            config.subtitles_enabled && !self.subtitles_forced_off,
+                        ),
+                        (GST_PLAY_FLAG_VIS, config.visualization),
+                        (GST_PLAY_FLAG_SOFT_VOLUME, config.soft_volume),
+                        (
+                            GST_PLAY_FLAG_DEINTERLACE,
+                            config.deinterlace != Deinterlace::Off,
+                        ),
+                        (GST_PLAY_FLAG_SOFT_COLORBALANCE, config.soft_colorbalance),
+                    ] {
+                        if enabled {
+                            flags |= flag;
+                        } else {
+                            flags &= !flag;
+                        }
+                    }
+                    match gst::glib::Value::from(flags).transform_with_type(flags_value.type_()) {
+                        Ok(value) => pipeline.set_property("flags", value),
+                        Err(err) => {
+                            log::warn!("failed to transform int to flags: {err}");
+                        }
+                    }
+                }
+                Err(err) => {
+                    log::warn!("failed to get flags as int: {err}");
+                }
+            },
+            Err(err) => {
+                log::warn!("failed to transform flags to int: {err}");
+            }
+        }
+        println!("updated flags {:?}", pipeline.property_value("flags"));
+    }
+
+    /// Returns the volume including any boost above one
+    fn volume(&self) -> f64 {
+        self.video_opt
+            .as_ref()
+            .map_or(0.0, |video| video.volume() * self.volume_boost)
+    }
+
+    /// Sets the volume, using the boost element of the audio filter for volumes above one
+    fn set_volume(&mut self, volume: f64) {
+        let Some(video) = &mut self.video_opt else {
+            return;
+        };
+        let boost_opt = video.pipeline().by_name("boost");
+        let max_volume = if boost_opt.is_some() {
+            self.flags.config.max_volume.max(1.0)
+        } else {
+            1.0
+        };
+        let volume = volume.clamp(0.0, max_volume);
+        if volume > 1.0 {
+            video.set_volume(1.0);
+            self.volume_boost = volume;
+        } else {
+            video.set_volume(volume);
+            self.volume_boost = 1.0;
+        }
+        if let Some(boost) = boost_opt {
+            boost.set_property("volume", self.volume_boost);
+        }
+    }
+
+    /// Crops the frame to the zoomed area and scales it back to the original size
+    fn update_zoom(&self) {
+        let Some(video) = &self.video_opt else {
+            return;
+        };
+        let pipeline = video.pipeline();
+        let Some(crop) = pipeline.by_name("zoom") else {
+            log::warn!("failed to find zoom element");
+            return;
+        };
+        let (width, height) = video.size();
+        if width <= 0 || height <= 0 {
+            return;
+        }
+
+        // Keep the sink at the original size so the player texture does not change
+        if let Some(sink) = pipeline.by_name("iced_video") {
+            let caps = gst::Caps::builder("video/x-raw")
+                .field("format", "NV12")
+                .field("pixel-aspect-ratio", gst::Fraction::new(1, 1))
+                .field("width", width)
+                .field("height", height)
+                .build();
+            sink.set_property("caps", &caps);
+        }
+
+        let hidden = 1.0 - 1.0 / self.zoom;
+        let (pan_x, pan_y) = self.pan;
+        for (size, pan, start, end) in [
+            (width, pan_x, "left", "right"),
+            (height, pan_y, "top", "bottom"),
+        ] {
+            let crop_total = (f64::from(size) * hidden).round() as i32;
+            let crop_start = (f64::from(crop_total) * (pan + 1.0) / 2.0).round() as i32;
+            crop.set_property(start, crop_start);
+            crop.set_property(end, crop_total - crop_start);
+        }
+    }
+
+    fn update_controls(&mut self, in_use: bool) {
+        if in_use || self.controls_hover {
+            self.controls = true;
+            self.controls_time = Instant::now();
+        } else if self.controls
+            && self.flags.config.controls_timeout > 0
+            && self.controls_time.elapsed()
+                > Duration::from_secs(self.flags.config.controls_timeout)
+        {
+            self.controls = false;
+        }
+    }
+
+    /// Re-query the duration periodically, for files that are still growing
+    fn update_duration(&mut self) {
+        if self.duration_time.elapsed() < DURATION_INTERVAL {
+            return;
+        }
+        self.duration_time = Instant::now();
+
+        let Some(video) = &self.video_opt else {
+            return;
+        };
+        if let Some(duration) = video.pipeline().query_duration::<gst::ClockTime>() {
+            let duration = duration.seconds_f64();
+            if duration > self.duration {
+                log::debug!("duration changed from {} to {}", self.duration, duration);
+                self.duration = duration;
+            }
+        }
+    }
+
+    /// Pauses while the buffering percentage is below 100, as recommended for network streams
+    fn update_buffering(&mut self) {
+        let Some(video) = &mut self.video_opt else {
+            return;
+        };
+        let percent = self.buffering.load(Ordering::Relaxed);
+        if percent < 100 {
+            if self.buffering_percent_opt.is_none() {
+                self.buffering_resume = !video.paused();
+                video.set_paused(true);
+            }
+            self.buffering_percent_opt = Some(percent);
+        } else if self
+            .buffering_percent_opt
+            .is_some_and(|percent| percent < 100)
+        {
+            if self.buffering_resume {
+                // The indicator is cleared by the first new frame
+                self.buffering_resume = false;
+                self.buffering_percent_opt = Some(100);
+                video.set_paused(false);
+            } else {
+                // No frame follows while paused
+                self.buffering_percent_opt = None;
+            }
+        }
+    }
+
+    fn update_stats(&mut self) {
+        if let Some(stats) = &mut self.stats_opt {
+            *stats = match &self.video_opt {
+                Some(video) => playback_stats(&video.pipeline()),
+                None => Vec::new(),
+            };
+        }
+    }
+
+    fn show_osd(&mut self, text: String) {
+        self.osd_opt = Some((text, Instant::now()));
+    }
+
+    fn update_osd(&mut self) {
+        if let Some((_, osd_time)) = &self.osd_opt {
+            if osd_time.elapsed() > OSD_TIMEOUT {
+                self.osd_opt = None;
+            }
+        }
+    }
+
+    /// Stores the position of the current file so it can be resumed, or forgets it near the start and end
+    fn save_position(&mut self) {
+        let (Some(url), Some(_)) = (&self.flags.url_opt, &self.video_opt) else {
+            return;
+        };
+        let position_opt = Some(self.position).filter(|&position| {
+            position > RESUME_MARGIN && position < self.duration - RESUME_MARGIN
+        });
+        let changed = self.flags.state.set_playback_position(url, position_opt);
+        self.position_save_time = Instant::now();
+        if changed {
+            self.save_state();
+        }
+    }
+
+    /// Moves the file to the front of the recent files
+    fn add_recent_file(&mut self, url: url::Url, title_opt: Option<String>) {
+        let recent_files = &mut self.flags.state.recent_files;
+        recent_files.retain(|recent_file| recent_file.url != url);
+        recent_files.push_front(RecentFile { url, title_opt });
+        recent_files.truncate(RECENT_FILES_LIMIT);
+        self.save_state();
+    }
+
+    fn save_state(&self) {
+        if let Some(state_handler) = &self.flags.state_handler {
+            if let Err(err) = self.flags.state.write_entry(state_handler) {
+                log::error!("failed to save config state: {}", err);
+            }
+        }
+    }
+
+    fn save_config(&mut self) -> Command<Message> {
+        if let Some(config_handler) = &self.flags.config_handler {
+            if let Err(err) = self.flags.config.write_entry(config_handler) {
+                log::error!("failed to save config: {}", err);
+            }
+        }
+        self.update_config()
+    }
+
+    fn update_config(&mut self) -> Command<Message> {
+        self.key_binds = key_binds(&self.flags.config.keybinds);
+        cosmic::app::command::set_theme(self.flags.config.app_theme.theme())
+    }
+
+    fn settings(&self) -> Element<Message> {
+        let app_theme_selected = match self.flags.config.app_theme {
+            AppTheme::Dark => 1,
+            AppTheme::Light => 2,
+            AppTheme::System => 0,
+        };
+        widget::settings::view_column(vec![
+            widget::settings::view_section(fl!("appearance"))
+                .add(
+                    widget::settings::item::builder(fl!("theme")).control(widget::dropdown(
+                        &self.app_themes,
+                        Some(app_theme_selected),
+                        move |index| {
+                            Message::AppTheme(match index {
+                                1 => AppTheme::Dark,
+                                2 => AppTheme::Light,
+                                _ => AppTheme::System,
+                            })
+                        },
+                    )),
+                )
+                .into(),
+            widget::settings::view_section(fl!("general"))
+                .add(
+                    widget::settings::item::builder(fl!("controls-timeout")).control(
+                        widget::dropdown(
+                            &self.controls_timeouts,
+                            CONTROLS_TIMEOUTS
+                                .iter()
+                                .position(|&timeout| timeout == self.flags.config.controls_timeout),
+                            |index| Message::ControlsTimeout(CONTROLS_TIMEOUTS[index]),
+                        ),
+                    ),
+                )
+                .add(
+                    widget::settings::item::builder(fl!("open-in-new-window")).toggler(
+                        self.flags.config.open_in_new_window,
+                        Message::OpenInNewWindow,
+                    ),
+                )
+                .add(
+                    widget::settings::item::builder(fl!("pause-on-unfocus"))
+                        .toggler(self.flags.config.pause_on_unfocus, Message::PauseOnUnfocus),
+                )
+                .add(
+                    widget::settings::item::builder(fl!("notify-on-finish"))
+                        .toggler(self.flags.config.notify_on_finish, Message::NotifyOnFinish),
+                )
+                .add(
+                    widget::settings::item::builder(fl!("scroll-seek"))
+                        .toggler(self.flags.config.scroll_seek, Message::ScrollSeek),
+                )
+                .add(
+                    widget::settings::item::builder(fl!("title-progress"))
+                        .toggler(self.flags.config.title_progress, Message::TitleProgress),
+                )
+                .into(),
+            widget::settings::view_section(fl!("rendering"))
+                .add(
+                    widget::settings::item::builder(fl!("update-rate")).control(widget::dropdown(
+                        &self.update_rates,
+                        UPDATE_RATES
+                            .iter()
+                            .position(|&rate| rate == self.flags.config.update_rate),
+                        |index| Message::UpdateRate(UPDATE_RATES[index]),
+                    )),
+                )
+                .add(
+                    widget::settings::item::builder(fl!("scrub-preview"))
+                        .toggler(self.flags.config.scrub_preview, Message::ScrubPreview),
+                )
+                .add(
+                    widget::settings::item::builder(fl!("hardware-decoding")).toggler(
+                        self.flags.config.hardware_decoding,
+                        Message::HardwareDecoding,
+                    ),
+                )
+                .add(
+                    widget::settings::item::builder(fl!("auto-rotate"))
+                        .toggler(self.flags.config.auto_rotate, Message::AutoRotate),
+                )
+                .add(
+                    widget::settings::item::builder(fl!("tone-mapping"))
+                        .toggler(self.flags.config.tone_mapping, Message::ToneMapping),
+                )
+                .add(
+                    widget::settings::item::builder(fl!("styled-subtitles"))
+                        .toggler(self.flags.config.styled_subtitles, Message::StyledSubtitles),
+                )
+                .add(
+                    widget::settings::item::builder(fl!("deinterlace")).control(widget::dropdown(
+                        &self.deinterlace_modes,
+                        Some(match self.flags.config.deinterlace {
+                            Deinterlace::Auto => 0,
+                            Deinterlace::On => 1,
+                            Deinterlace::Off => 2,
+                        }),
+                        |index| {
+                            Message::Deinterlace(match index {
+                                1 => Deinterlace::On,
+                                2 => Deinterlace::Off,
+                                _ => Deinterlace::Auto,
+                            })
+                        },
+                    )),
+                )
+                .add(
+                    widget::settings::item::builder(fl!("software-volume"))
+                        .toggler(self.flags.config.soft_volume, |enabled| {
+                            Message::PlayFlag(GST_PLAY_FLAG_SOFT_VOLUME, enabled)
+                        }),
+                )
+                .add(
+                    widget::settings::item::builder(fl!("software-color-balance"))
+                        .toggler(self.flags.config.soft_colorbalance, |enabled| {
+                            Message::PlayFlag(GST_PLAY_FLAG_SOFT_COLORBALANCE, enabled)
+                        }),
+                )
+                .add(
+                    widget::settings::item::builder(fl!("visualization"))
+                        .toggler(self.flags.config.visualization, |enabled| {
+                            Message::PlayFlag(GST_PLAY_FLAG_VIS, enabled)
+                        }),
+                )
+                .into(),
+        ])
+        .into()
+    }
+
+    fn progress_percent(&self) -> Option<u32> {
+        if self.duration > 0.0 {
+            Some(((self.position / self.duration) * 100.0).clamp(0.0, 100.0) as u32)
+        } else {
+            None
+        }
+    }
+
+    fn seek_slider(&self) -> Element<Message> {
+        let duration = self.duration;
+        let position = self.position;
+
+        let mut markers = Vec::with_capacity(3);
+        if let Some(loop_a) = self.loop_a {
+            markers.push((loop_a, "A".to_string()));
+        }
+        if let Some(loop_b) = self.loop_b {
+            markers.push((loop_b, "B".to_string()));
+        }
+        if let Some(seek_hover) = self.seek_hover_opt {
+            markers.push((seek_hover, format_time(seek_hover)));
+        }
+        markers.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let height = if markers.is_empty() {
+            SEEK_SLIDER_HEIGHT
+        } else {
+            SEEK_SLIDER_HEIGHT + SEEK_MARKER_HEIGHT
+        };
+
+        // The slider width is needed to map the cursor to a time
+        let responsive = iced_widget::responsive(move |size| {
+            let slider = Slider::new(0.0..=duration, position, Message::Seek)
+                .step(0.1)
+                .height(SEEK_SLIDER_HEIGHT)
+                .on_release(Message::SeekRelease);
+            let hover_area = iced_widget::mouse_area(slider)
+                .on_move(move |point| {
+                    if duration > 0.0 && size.width > 0.0 {
+                        let secs = (point.x / size.width).clamp(0.0, 1.0) as f64 * duration;
+                        Message::SeekHover(Some(secs))
+                    } else {
+                        Message::SeekHover(None)
+                    }
+                })
+                .on_exit(Message::SeekHover(None));
+            if markers.is_empty() {
+                return hover_area.into();
+            }
+
+            // Place markers above the slider, in thousandths of the duration
+            let portion = |secs: f64| -> u16 {
+                if duration > 0.0 {
+                    ((secs / duration).clamp(0.0, 1.0) * 1000.0) as u16
+                } else {
+                    0
+                }
+            };
+            let mut row = widget::row::with_capacity(markers.len() * 2 + 1)
+                .height(Length::Fixed(SEEK_MARKER_HEIGHT));
+            let mut last = 0;
+            for (secs, label) in markers.iter() {
+                let next = portion(*secs).max(last);
+                row = row
+                    .push(widget::horizontal_space(Length::FillPortion(next - last)))
+                    .push(widget::text(label.clone()).size(10).font(font::mono()));
+                last = next;
+            }
+            row = row.push(widget::horizontal_space(Length::FillPortion(1000 - last)));
+
+            widget::column::with_children(vec![row.into(), hover_area.into()])
+                .width(Length::Fill)
+                .into()
+        });
+
+        widget::container(responsive)
+            .width(Length::Fill)
+            .height(Length::Fixed(height))
+            .into()
+    }
+
+    fn url_dialog(&self) -> Option<Element<Message>> {
+        let url_dialog = self.url_dialog_opt.as_ref()?;
+        let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
+
+        let mut column = widget::column::with_capacity(2).spacing(space_xxs);
+        column = column.push(
+            widget::text_input(fl!("url-placeholder"), url_dialog.input.as_str())
+                .id(self.url_input_id.clone())
+                .on_input(Message::UrlInput)
+                .on_submit(Message::UrlSubmit),
+        );
+        if let Some(error) = &url_dialog.error_opt {
+            column = column.push(widget::text(fl!("invalid-url", error = error.as_str())));
+        }
+
+        Some(
+            widget::dialog(fl!("open-network-stream"))
+                .control(column)
+                .primary_action(widget::button::suggested(fl!("open")).on_press(Message::UrlSubmit))
+                .secondary_action(
+                    widget::button::standard(fl!("cancel")).on_press(Message::UrlCancel),
+                )
+                .into(),
+        )
+    }
+
+    fn view_player(&self) -> Element<Message> {
+        let cosmic_theme::Spacing {
+            space_xxs,
+            space_xs,
+            space_m,
+            ..
+        } = theme::active().cosmic().spacing;
+
+        let Some(video) = &self.video_opt else {
+            let mut column = widget::column::with_capacity(3)
+                .align_items(Alignment::Center)
+                .spacing(space_xs);
+            if let Some(error) = &self.error_opt {
+                column = column.push(
+                    widget::container(
+                        widget::row::with_capacity(3)
+                            .push(widget::text(error.as_str()))
+                            .push_maybe(self.error_retry.then(|| {
+                                widget::button::standard(fl!("retry")).on_press(Message::Reload)
+                            }))
+                            .push(
+                                widget::button::icon(
+                                    widget::icon::from_name("window-close-symbolic").size(16),
+                                )
+                                .on_press(Message::ErrorDismiss),
+                            )
+                            .align_items(Alignment::Center)
+                            .spacing(space_xxs),
+                    )
+                    .padding([space_xxs, space_xs])
+                    .style(theme::Container::Card),
+                );
+            } else {
+                column = column.push(widget::text(fl!("no-video-open")));
+            }
+            column = column
+                .push(widget::button::suggested(fl!("open-media")).on_press(Message::FileOpen));
+            return widget::container(column)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center_x()
+                .center_y()
+                .style(theme::Container::WindowBackground)
+                .into();
+        };
+
+        let muted = video.muted();
+        let volume = self.volume();
+
+        let mut video_player = VideoPlayer::new(video)
+            .content_fit(self.aspect_mode.content_fit())
+            .mouse_hidden(!self.controls)
+            .on_end_of_stream(Message::EndOfStream)
+            .on_missing_plugin(Message::MissingPlugin)
+            .width(Length::Fill)
+            .height(Length::Fill);
+        if self.flags.config.update_rate == 0 {
+            video_player = video_player.on_new_frame(Message::NewFrame);
+        }
+
+        let mouse_area = widget::mouse_area(video_player)
+            .on_press(Message::PlayPause)
+            .on_double_press(Message::Fullscreen);
+
+        let mut content: Element<_> = mouse_area.into();
+        if let Some(stats) = self.stats_opt.as_ref().filter(|stats| !stats.is_empty()) {
+            content = widget::popover(content)
+                .popup(
+                    widget::container(widget::column::with_children(
+                        stats
+                            .iter()
+                            .map(|line| widget::text(line.as_str()).font(font::mono()).into())
+                            .collect(),
+                    ))
+                    .padding([space_xxs, space_xs])
+                    .style(theme::Container::Custom(Box::new(|_theme| {
+                        widget::container::Appearance {
+                            text_color: Some(Color::WHITE),
+                            background: Some(Background::Color(Color::from_rgba(
+                                0.0, 0.0, 0.0, 0.6,
+                            ))),
+                            ..Default::default()
+                        }
+                    }))),
+                )
+                .position(widget::popover::Position::Point(Point::new(
+                    f32::from(space_xs),
+                    f32::from(space_xs),
+                )))
+                .into();
+        }
+        let osd_opt = match self.buffering_percent_opt {
+            Some(percent) => Some(fl!("buffering", percent = percent)),
+            None => self.osd_opt.as_ref().map(|(osd, _)| osd.clone()),
+        };
+        if let Some(osd) = osd_opt {
+            content = widget::popover(content)
+                .popup(
+                    widget::container(widget::text::title4(osd))
+                        .padding([space_xxs, space_m])
+                        .style(theme::Container::WindowBackground),
+                )
+                .position(widget::popover::Position::Center)
+                .into();
+        }
+
+        let mut popover = widget::popover(content).position(widget::popover::Position::Bottom);
+        let mut popup_items = Vec::<Element<_>>::with_capacity(3);
+        if self.plugin_install_pending {
+            popup_items.push(
+                widget::container(widget::text(fl!("installing-plugins")))
+                    .padding([space_xxs, space_xs])
+                    .style(theme::Container::WindowBackground)
+                    .into(),
+            );
+        }
+        if let Some(notice) = &self.notice_opt {
+            popup_items.push(
+                widget::container(
+                    widget::row::with_children(vec![
+                        widget::text(notice.as_str()).into(),
+                        widget::horizontal_space(Length::Fill).into(),
+                        widget::button::icon(
+                            widget::icon::from_name("window-close-symbolic").size(16),
+                        )
+                        .on_press(Message::NoticeDismiss)
+                        .into(),
+                    ])
+                    .align_items(Alignment::Center)
+                    .spacing(space_xxs),
+                )
+                .padding([space_xxs, space_xs])
+                .style(theme::Container::WindowBackground)
+                .into(),
+            );
+        }
+        if let Some(dropdown) = self.dropdown_opt {
+            let mut items = Vec::<Element<_>>::new();
+            match dropdown {
+                DropdownKind::Audio => {
+                    items.push(
+                        widget::row::with_children(vec![
+                            widget::button::icon(
+                                widget::icon::from_name({
+                                    if muted {
+                                        "audio-volume-muted-symbolic"
+                                    } else {
+                                        if volume >= (2.0 / 3.0) {
+                                            "audio-volume-high-symbolic"
+                                        } else if volume >= (1.0 / 3.0) {
+                                            "audio-volume-medium-symbolic"
+                                        } else {
+                                            "audio-volume-low-symbolic"
+                                        }
+                                    }
+                                })
+                                .size(16),
+                            )
+                            .on_press(Message::AudioToggle)
+                            .into(),
+                            //TODO: disable slider when muted?
+                            Slider::new(
+                                0.0..=self.flags.config.max_volume.max(1.0),
+                                volume,
+                                Message::AudioVolume,
+                            )
+                            .step(0.01)
+                            .into(),
+                        ])
+                        .align_items(Alignment::Center)
+                        .into(),
+                    );
+                    let track_gain = self.track_gain();
+                    items.push(
+                        widget::text::heading(fl!(
+                            "track-gain",
+                            gain = format!("{:+.1}", track_gain)
+                        ))
+                        .into(),
+                    );
+                    items.push(
+                        Slider::new(
+                            -MAX_TRACK_GAIN..=MAX_TRACK_GAIN,
+                            track_gain,
+                            Message::TrackGain,
+                        )
+                        .step(0.5)
+                        .into(),
+                    );
+                    items.push(
+                        widget::toggler(
+                            fl!("normalize-volume"),
+                            self.flags.config.normalize_volume,
+                            Message::NormalizeVolume,
+                        )
+                        .into(),
+                    );
+                    items.push(widget::text::heading(fl!("max-volume")).into());
+                    items.push(
+                        widget::dropdown(
+                            &self.max_volumes,
+                            MAX_VOLUMES
+                                .iter()
+                                .position(|&max_volume| max_volume == self.flags.config.max_volume),
+                            |index| Message::MaxVolume(MAX_VOLUMES[index]),
+                        )
+                        .into(),
+                    );
+                    items.push(
+                        widget::button::text(fl!("equalizer"))
+                            .on_press(Message::DropdownToggle(DropdownKind::Equalizer))
+                            .into(),
+                    );
+                    if self.audio_devices.len() > 1 {
+                        items.push(widget::text::heading(fl!("output-device")).into());
+                        items.push(
+                            widget::dropdown(
+                                &self.audio_devices,
+                                Some(
+                                    self.flags
+                                        .config
+                                        .audio_device
+                                        .as_ref()
+                                        .and_then(|name| {
+                                            self.audio_devices
+                                                .iter()
+                                                .skip(1)
+                                                .position(|device| device == name)
+                                                .map(|index| index + 1)
+                                        })
+                                        .unwrap_or(0),
+                                ),
+                                Message::AudioDevice,
+                            )
+                            .into(),
+                        );
+                    }
+                }
+                DropdownKind::Equalizer => {
+                    items.push(widget::text::heading(fl!("equalizer")).into());
+                    let mut row =
+                        widget::row::with_capacity(EQUALIZER_BANDS.len()).spacing(space_xxs);
+                    for (band, label) in EQUALIZER_BANDS.iter().enumerate() {
+                        row = row.push(
+                            widget::column::with_children(vec![
+                                vertical_slider(
+                                    -24.0..=12.0,
+                                    self.flags.config.equalizer[band],
+                                    move |gain| Message::EqualizerBand(band, gain),
+                                )
+                                .step(0.5)
+                                .height(Length::Fixed(120.0))
+                                .into(),
+                                widget::text(*label).size(10).into(),
+                            ])
+                            .align_items(Alignment::Center)
+                            .width(Length::Fill),
+                        );
+                    }
+                    items.push(row.into());
+                }
+                DropdownKind::ColorBalance => {
+                    items.push(widget::text::heading(fl!("color-balance")).into());
+                    for (index, label) in [
+                        fl!("brightness"),
+                        fl!("contrast"),
+                        fl!("hue"),
+                        fl!("saturation"),
+                    ]
+                    .into_iter()
+                    .enumerate()
+                    {
+                        items.push(widget::text(label).into());
+                        items.push(
+                            Slider::new(
+                                -1.0..=1.0,
+                                self.flags.config.color_balance[index],
+                                move |value| Message::ColorBalance(index, value),
+                            )
+                            .step(0.01)
+                            .into(),
+                        );
+                    }
+                    items.push(
+                        widget::button::standard(fl!("reset"))
+                            .on_press(Message::ColorBalanceReset)
+                            .into(),
+                    );
+                }
+                DropdownKind::Subtitle => {
+                    // Only offer a choice if the manifest lists more than one variant
+                    if self.stream_variants.len() > 2 {
+                        items.push(widget::text::heading(fl!("quality")).into());
+                        items.push(
+                            widget::dropdown(
+                                &self.stream_variants,
+                                self.stream_variant_speeds
+                                    .iter()
+                                    .position(|&speed| speed == self.connection_speed),
+                                Message::SetStreamVariant,
+                            )
+                            .into(),
+                        );
+                    }
+                    if !self.audio_codes.is_empty() {
+                        items.push(widget::text::heading(fl!("audio")).into());
+                        items.push(
+                            widget::dropdown(
+                                &self.audio_codes,
+                                usize::try_from(self.current_audio).ok(),
+                                Message::AudioCode,
+                            )
+                            .into(),
+                        );
+                    }
+                    if self.text_codes.len() > 1 {
+                        items.push(widget::text::heading(fl!("subtitles")).into());
+                        items.push(
+                            widget::dropdown(
+                                &self.text_codes,
+                                if self.flags.config.subtitles_enabled && !self.subtitles_forced_off
+                                {
+                                    usize::try_from(self.current_text + 1).ok()
+                                } else {
+                                    Some(0)
+                                },
+                                Message::TextCode,
+                            )
+                            .into(),
+                        );
+                        items.push(widget::text::heading(fl!("subtitle-position")).into());
+                        items.push(
+                            Slider::new(
+                                0..=200,
+                                self.flags.config.subtitle_position,
+                                Message::SubtitlePosition,
+                            )
+                            .into(),
+                        );
+                        items.push(
+                            widget::text::heading(if self.flags.config.subtitle_size == 0 {
+                                fl!("subtitle-size-default")
+                            } else {
+                                fl!("subtitle-size", size = self.flags.config.subtitle_size)
+                            })
+                            .into(),
+                        );
+                        items.push(
+                            Slider::new(
+                                0..=MAX_SUBTITLE_SIZE,
+                                self.flags.config.subtitle_size,
+                                Message::SubtitleSize,
+                            )
+                            .into(),
+                        );
+                    }
+                }
+            }
+
+            let mut column = widget::column::with_capacity(items.len());
+            for item in items {
+                column = column.push(widget::container(item).padding([space_xxs, space_m]));
+            }
+
+            popup_items.push(
+                widget::row::with_children(vec![
+                    widget::horizontal_space(Length::Fill).into(),
+                    widget::container(column)
+                        .padding(1)
+                        //TODO: move style to libcosmic
+                        .style(theme::Container::custom(|theme| {
+                            let cosmic = theme.cosmic();
+                            let component = &cosmic.background.component;
+                            widget::container::Appearance {
+                                icon_color: Some(component.on.into()),
+                                text_color: Some(component.on.into()),
+                                background: Some(Background::Color(component.base.into())),
+                                border: Border {
+                                    radius: 8.0.into(),
+                                    width: 1.0,
+                                    color: component.divider.into(),
+                                },
+                                ..Default::default()
+                            }
+                        }))
+                        .width(Length::Fixed(240.0))
+                        .into(),
+                ])
+                .into(),
+            );
+        }
+        if self.controls {
+            // Time labels are replaced by the seek hover time when condensed
+            let condensed = self.core.is_condensed();
+            popup_items.push(
+                widget::container(
+                    widget::row::with_capacity(9)
+                        .align_items(Alignment::Center)
+                        .spacing(space_xxs)
+                        .push(
+                            widget::button::icon(
+                                if self.video_opt.as_ref().map_or(true, |video| video.paused()) {
+                                    widget::icon::from_name("media-playback-start-symbolic")
+                                        .size(16)
+                                } else {
+                                    widget::icon::from_name("media-playback-pause-symbolic")
+                                        .size(16)
+                                },
+                            )
+                            .on_press(Message::PlayPause),
+                        )
+                        .push_maybe(
+                            (!condensed).then(|| {
+                                widget::text(format_time(self.position)).font(font::mono())
+                            }),
+                        )
+                        .push(self.seek_slider())
+                        .push_maybe((!condensed).then(|| {
+                            widget::text(format_time(self.duration - self.position))
+                                .font(font::mono())
+                        }))
+                        .push_maybe(self.sleep_timer_opt.map(|sleep_timer| {
+                            widget::text(match sleep_timer {
+                                SleepTimer::At(sleep_time) => format_time(
+                                    sleep_time
+                                        .saturating_duration_since(Instant::now())
+                                        .as_secs_f64(),
+                                ),
+                                SleepTimer::EndOfFile => fl!("sleep-timer-end-of-file-short"),
+                            })
+                            .font(font::mono())
+                        }))
+                        .push(
+                            widget::button::icon(
+                                widget::icon::from_name("media-playlist-repeat-symbolic").size(16),
+                            )
+                            .selected(self.flags.config.looping)
+                            .on_press(Message::ToggleLoop),
+                        )
+                        .push(
+                            widget::button::icon(
+                                widget::icon::from_name("media-view-subtitles-symbolic").size(16),
+                            )
+                            .on_press(Message::DropdownToggle(DropdownKind::Subtitle)),
+                        )
+                        .push(
+                            widget::button::icon(
+                                widget::icon::from_name("view-fullscreen-symbolic").size(16),
+                            )
+                            .on_press(Message::Fullscreen),
+                        )
+                        .push(
+                            //TODO: scroll up/down on icon to change volume
+                            widget::button::icon(
+                                widget::icon::from_name({
+                                    if muted {
+                                        "audio-volume-muted-symbolic"
+                                    } else {
+                                        if volume >= (2.0 / 3.0) {
+                                            "audio-volume-high-symbolic"
+                                        } else if volume >= (1.0 / 3.0) {
+                                            "audio-volume-medium-symbolic"
+                                        } else {
+                                            "audio-volume-low-symbolic"
+                                        }
+                                    }
+                                })
+                                .size(16),
+                            )
+                            .on_press(Message::DropdownToggle(DropdownKind::Audio)),
+                        ),
+                )
+                .padding([space_xxs, space_xs])
+                .style(theme::Container::WindowBackground)
+                .into(),
+            );
+        }
+        if !popup_items.is_empty() {
+            // Hovering the controls or their dropdowns keeps them visible
+            popover = popover.popup(
+                iced_widget::mouse_area(widget::column::with_children(popup_items))
+                    .on_enter(Message::ControlsHover(true))
+                    .on_exit(Message::ControlsHover(false)),
+            );
+        }
+
+        widget::container(popover)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(theme::Container::Custom(Box::new(|_theme| {
+                widget::container::Appearance::default().with_background(Color::BLACK)
+            })))
+            .into()
+    }
+
+    fn update_title(&mut self) -> Command<Message> {
+        let mut title = "COSMIC Media Player".to_string();
+        if self.video_opt.is_some() {
+            // Prefer the title tag over the file name
+            let name_opt = self
+                .media_title_opt
+                .clone()
+                .or_else(|| self.flags.url_opt.as_ref().map(url_name));
+            if let Some(name) = name_opt {
+                title = format!("{} — {}", name, title);
+            }
+        }
+        self.title_percent_opt = None;
+        if self.flags.config.title_progress {
+            self.title_percent_opt = self.progress_percent();
+            if let Some(percent) = self.title_percent_opt {
+                title = format!("{}% — {}", percent, title);
+            }
+        }
+        self.set_window_title(title)
+    }
+}
+
+/// Implement [`cosmic::Application`] to integrate with COSMIC.
+impl Application for App {
+    /// Default async executor to use with the app.
+    type Executor = executor::Default;
+
+    /// Argument received [`cosmic::Application::new`].
+    type Flags = Flags;
+
+    /// Message type specific to our [`App`].
+    type Message = Message;
 
     /// The unique application ID to supply to the window manager.
     const APP_ID: &'static str = "com.system76.CosmicPlayer";
@@ -379,58 +3230,200 @@ impl Application for App {
     fn init(mut core: Core, flags: Self::Flags) -> (Self, Command<Self::Message>) {
         core.window.content_container = false;
 
+        let key_binds = key_binds(&flags.config.keybinds);
         let mut app = App {
             core,
             flags,
+            app_themes: vec![fl!("match-desktop"), fl!("dark"), fl!("light")],
+            deinterlace_modes: vec![
+                fl!("deinterlace-auto"),
+                fl!("deinterlace-on"),
+                fl!("deinterlace-off"),
+            ],
+            update_rates: UPDATE_RATES
+                .iter()
+                .map(|&rate| {
+                    if rate == 0 {
+                        fl!("update-rate-every-frame")
+                    } else {
+                        fl!("update-rate-per-second", rate = rate)
+                    }
+                })
+                .collect(),
+            controls_timeouts: CONTROLS_TIMEOUTS
+                .iter()
+                .map(|&timeout| {
+                    if timeout == 0 {
+                        fl!("controls-timeout-never")
+                    } else {
+                        fl!("controls-timeout-seconds", seconds = timeout)
+                    }
+                })
+                .collect(),
+            max_volumes: MAX_VOLUMES
+                .iter()
+                .map(|&max_volume| fl!("max-volume-percent", percent = max_volume * 100.0))
+                .collect(),
+            volume_boost: 1.0,
+            zoom: 1.0,
+            pan: (0.0, 0.0),
+            aspect_mode: AspectMode::Fit,
+            context_page: ContextPage::Settings,
             controls: true,
+            controls_hover: false,
             controls_time: Instant::now(),
             dropdown_opt: None,
             fullscreen: false,
-            key_binds: key_binds(),
+            mini_player: false,
+            key_binds,
             video_opt: None,
             position: 0.0,
             duration: 0.0,
+            duration_time: Instant::now(),
             dragging: false,
+            paused_on_scrub: false,
+            paused_on_unfocus: false,
+            subtitles_forced_off: false,
+            scrub_time: Instant::now(),
+            loop_a: None,
+            loop_b: None,
+            audio_devices: Vec::new(),
             audio_codes: Vec::new(),
+            audio_languages: Vec::new(),
             current_audio: -1,
             text_codes: Vec::new(),
+            text_languages: Vec::new(),
             current_text: -1,
+            subtitle_delay: 0,
+            audio_delay: 0,
+            adaptive_streaming: false,
+            buffering: Arc::new(AtomicI32::new(100)),
+            buffering_percent_opt: None,
+            buffering_resume: false,
+            network_stream: false,
+            media_title_opt: None,
+            stream_manifest: Arc::new(Mutex::new(Vec::new())),
+            stream_variants: Vec::new(),
+            stream_variant_speeds: Vec::new(),
+            connection_speed: 0,
+            notice_opt: None,
+            error_opt: None,
+            error_retry: false,
+            plugin_install_pending: false,
+            osd_opt: None,
+            stats_opt: None,
+            position_save_time: Instant::now(),
+            restart_opt: None,
+            restart_count: 0,
+            seek_hover_opt: None,
+            sleep_timer_opt: None,
+            title_percent_opt: None,
+            url_dialog_opt: None,
+            url_input_id: widget::Id::unique(),
+            window_resize_id: 0,
         };
 
-        let command = app.load();
-        (app, command)
+        let mut commands = vec![app.load()];
+        if app.flags.fullscreen {
+            commands.push(app.update(Message::Fullscreen));
+        } else if app.flags.state.mini_player {
+            commands.push(app.update(Message::MiniPlayer));
+        }
+        (app, Command::batch(commands))
     }
 
     fn on_escape(&mut self) -> Command<Self::Message> {
-        if self.fullscreen {
+        if self.url_dialog_opt.take().is_some() {
+            Command::none()
+        } else if self.fullscreen {
             return self.update(Message::Fullscreen);
+        } else if self.mini_player {
+            return self.update(Message::MiniPlayer);
         } else {
             Command::none()
         }
     }
 
+    /// Closing the window goes through the same cleanup as the Quit menu item
+    fn on_close_requested(&self, _id: window::Id) -> Option<Self::Message> {
+        Some(Message::WindowClose)
+    }
+
     /// Handle application events here.
     fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
         match message {
             Message::None => {}
+            Message::AppTheme(app_theme) => {
+                self.flags.config.app_theme = app_theme;
+                return self.save_config();
+            }
             Message::Config(config) => {
                 if config != self.flags.config {
                     log::info!("update config");
                     self.flags.config = config;
+                    // Subtitle colors, position and font can change on the current file
+                    self.update_elements();
+                    self.update_subtitle_font();
                     return self.update_config();
                 }
             }
+            Message::Deinterlace(deinterlace) => {
+                self.flags.config.deinterlace = deinterlace;
+                self.update_flags();
+                self.update_elements();
+                return self.save_config();
+            }
+            Message::Pan(x, y) => {
+                self.pan = (
+                    (self.pan.0 + x).clamp(-1.0, 1.0),
+                    (self.pan.1 + y).clamp(-1.0, 1.0),
+                );
+                self.update_zoom();
+            }
+            Message::Zoom(factor) => {
+                self.zoom = (self.zoom * factor).clamp(1.0, MAX_ZOOM);
+                if self.zoom == 1.0 {
+                    self.pan = (0.0, 0.0);
+                }
+                self.update_zoom();
+                self.show_osd(fl!(
+                    "zoom-percent",
+                    percent = (self.zoom * 100.0).round() as i64
+                ));
+            }
+            Message::ZoomReset => {
+                self.zoom = 1.0;
+                self.pan = (0.0, 0.0);
+                self.update_zoom();
+                self.show_osd(fl!("zoom-percent", percent = 100));
+            }
+            Message::CycleAspect => {
+                self.aspect_mode = self.aspect_mode.next();
+                self.show_osd(fl!("aspect-mode", mode = self.aspect_mode.name()));
+            }
             Message::DropdownToggle(menu_kind) => {
                 if self.dropdown_opt.take() != Some(menu_kind) {
                     self.dropdown_opt = Some(menu_kind);
+                    if menu_kind == DropdownKind::Audio {
+                        self.update_audio_devices();
+                    }
                 }
             }
             Message::FileClose => {
                 self.close();
+                return self.update_title();
             }
             Message::FileLoad(url) => {
-                self.flags.url_opt = Some(url);
-                return self.load();
+                if self.flags.config.open_in_new_window && self.video_opt.is_some() {
+                    spawn_window(&url);
+                } else {
+                    // Close first so the position is stored for the previous file
+                    self.close();
+                    // Variants of the previous stream do not apply
+                    self.connection_speed = 0;
+                    self.flags.url_opt = Some(url);
+                    return self.load();
+                }
             }
             Message::FileOpen => {
                 //TODO: embed cosmic-files dialog (after libcosmic rebase works)
@@ -452,12 +3445,153 @@ impl Application for App {
                     |x| x,
                 );
             }
+            Message::FileOpenRecent(index) => {
+                if let Some(recent_file) = self.flags.state.recent_files.get(index) {
+                    return self.update(Message::FileLoad(recent_file.url.clone()));
+                }
+            }
+            Message::FileReload => {
+                // Continue from the current position, unless it is a live stream
+                if self.video_opt.is_some() && self.duration > 0.0 {
+                    self.flags.start_opt = Duration::try_from_secs_f64(self.position).ok();
+                }
+                return self.load();
+            }
+            Message::FolderLoad(path) => {
+                //TODO: build a playlist from the whole folder
+                let mut paths = Vec::new();
+                media_files(
+                    &path,
+                    &self.flags.config,
+                    self.flags.config.recursive_scan_depth,
+                    &mut paths,
+                );
+                paths.sort();
+                match paths.first().map(url::Url::from_file_path) {
+                    Some(Ok(url)) => return self.update(Message::FileLoad(url)),
+                    Some(Err(())) => {
+                        log::warn!("failed to convert {:?} to URL", paths[0]);
+                    }
+                    None => {
+                        log::warn!("no media files found in {:?}", path);
+                    }
+                }
+            }
+            Message::ScrollSeek(scroll_seek) => {
+                self.flags.config.scroll_seek = scroll_seek;
+                return self.save_config();
+            }
+            Message::Scrolled(delta) => {
+                // Mouse wheels scroll by lines, touchpads by pixels
+                let (x, y) = match delta {
+                    ScrollDelta::Lines { x, y } => (x, y),
+                    ScrollDelta::Pixels { x, y } => (x / 20.0, y / 20.0),
+                };
+                if self.flags.config.scroll_seek && x.abs() > y.abs() {
+                    // Negative horizontal deltas scroll to the right
+                    return self.update(Message::SeekRelative(
+                        -f64::from(x) * self.flags.config.scroll_seek_step,
+                    ));
+                } else if y != 0.0 {
+                    return self.update(Message::AudioVolumeStep(
+                        f64::from(y) * self.flags.config.volume_scroll_step,
+                    ));
+                }
+            }
+            Message::ScrubPreview(scrub_preview) => {
+                self.flags.config.scrub_preview = scrub_preview;
+                return self.save_config();
+            }
+            Message::OpenContainingFolder => {
+                if let Some(url) = &self.flags.url_opt {
+                    open_containing_folder(url);
+                }
+            }
+            Message::OpenUrlDialog => {
+                self.url_dialog_opt = Some(UrlDialog::default());
+                return widget::text_input::focus(self.url_input_id.clone());
+            }
+            Message::UrlCancel => {
+                self.url_dialog_opt = None;
+            }
+            Message::UrlInput(input) => {
+                if let Some(url_dialog) = &mut self.url_dialog_opt {
+                    url_dialog.input = input;
+                    url_dialog.error_opt = None;
+                }
+            }
+            Message::UrlSubmit => {
+                if let Some(url_dialog) = &mut self.url_dialog_opt {
+                    match url::Url::parse(url_dialog.input.trim()) {
+                        Ok(url) => {
+                            self.url_dialog_opt = None;
+                            return self.update(Message::FileLoad(url));
+                        }
+                        Err(err) => {
+                            url_dialog.error_opt = Some(err.to_string());
+                        }
+                    }
+                }
+            }
+            Message::RequestSubtitles => {
+                let (Some(command), Some(url)) = (
+                    self.flags.config.subtitle_command.clone(),
+                    self.flags.url_opt.clone(),
+                ) else {
+                    return Command::none();
+                };
+                self.show_osd(fl!("finding-subtitles"));
+                return Command::perform(
+                    async move {
+                        let result = tokio::task::spawn_blocking(move || {
+                            run_subtitle_command(&command, &url)
+                        })
+                        .await;
+                        match result {
+                            Ok(Ok(path)) => message::app(Message::LoadSubtitle(path)),
+                            Ok(Err(err)) => message::app(Message::RequestSubtitlesFailed(err)),
+                            Err(err) => {
+                                log::warn!("failed to run subtitle command task: {}", err);
+                                message::none()
+                            }
+                        }
+                    },
+                    |x| x,
+                );
+            }
+            Message::RequestSubtitlesFailed(err) => {
+                log::warn!("{}", err);
+                self.show_osd(fl!("subtitles-not-found"));
+            }
+            Message::SubtitleOpen => {
+                #[cfg(feature = "xdg-portal")]
+                return Command::perform(
+                    async move {
+                        let dialog = cosmic::dialog::file_chooser::open::Dialog::new()
+                            .title(fl!("load-subtitles"));
+                        match dialog.open_file().await {
+                            Ok(response) => match response.url().to_file_path() {
+                                Ok(path) => message::app(Message::LoadSubtitle(path)),
+                                Err(()) => {
+                                    log::warn!("unsupported subtitle URL {}", response.url());
+                                    message::none()
+                                }
+                            },
+                            Err(err) => {
+                                log::warn!("failed to open subtitles: {}", err);
+                                message::none()
+                            }
+                        }
+                    },
+                    |x| x,
+                );
+            }
             Message::Fullscreen => {
                 //TODO: cleanest way to close dropdowns
                 self.dropdown_opt = None;
 
                 self.fullscreen = !self.fullscreen;
-                self.core.window.show_headerbar = !self.fullscreen;
+                self.core.window.show_headerbar = !self.fullscreen && !self.mini_player;
                 return window::change_mode(
                     window::Id::MAIN,
                     if self.fullscreen {
@@ -467,13 +3601,132 @@ impl Application for App {
                     },
                 );
             }
+            Message::MiniPlayer => {
+                self.dropdown_opt = None;
+
+                self.mini_player = !self.mini_player;
+                if self.flags.state.mini_player != self.mini_player {
+                    self.flags.state.mini_player = self.mini_player;
+                    self.save_state();
+                }
+
+                let mut commands = Vec::with_capacity(3);
+                if self.fullscreen {
+                    self.fullscreen = false;
+                    commands.push(window::change_mode(
+                        window::Id::MAIN,
+                        window::Mode::Windowed,
+                    ));
+                }
+                self.core.window.show_headerbar = !self.mini_player;
+                if self.mini_player {
+                    commands.push(window::resize(
+                        window::Id::MAIN,
+                        Size::new(MINI_PLAYER_WIDTH, MINI_PLAYER_HEIGHT),
+                    ));
+                    commands.push(window::change_level(
+                        window::Id::MAIN,
+                        window::Level::AlwaysOnTop,
+                    ));
+                } else {
+                    // Restore the stored windowed size
+                    commands.push(window::resize(
+                        window::Id::MAIN,
+                        Size::new(
+                            self.flags.config.window_width as f32,
+                            self.flags.config.window_height as f32,
+                        ),
+                    ));
+                    commands.push(window::change_level(
+                        window::Id::MAIN,
+                        window::Level::Normal,
+                    ));
+                }
+                return Command::batch(commands);
+            }
             Message::Key(modifiers, key) => {
+                // Arrow keys pan instead of seeking while zoomed in
+                if self.zoom > 1.0 && modifiers.is_empty() {
+                    let pan_opt = match key {
+                        Key::Named(Named::ArrowLeft) => Some((-PAN_STEP, 0.0)),
+                        Key::Named(Named::ArrowRight) => Some((PAN_STEP, 0.0)),
+                        Key::Named(Named::ArrowUp) => Some((0.0, -PAN_STEP)),
+                        Key::Named(Named::ArrowDown) => Some((0.0, PAN_STEP)),
+                        _ => None,
+                    };
+                    if let Some((x, y)) = pan_opt {
+                        return self.update(Message::Pan(x, y));
+                    }
+                }
                 for (key_bind, action) in self.key_binds.iter() {
                     if key_bind.matches(modifiers, &key) {
                         return self.update(action.message());
                     }
                 }
             }
+            Message::HardwareDecoding(hardware_decoding) => {
+                // Takes effect when the next file is loaded
+                self.flags.config.hardware_decoding = hardware_decoding;
+                return self.save_config();
+            }
+            Message::AutoRotate(auto_rotate) => {
+                // Rotation can change the frame size, so it takes effect when the next file is loaded
+                self.flags.config.auto_rotate = auto_rotate;
+                return self.save_config();
+            }
+            Message::ToneMapping(tone_mapping) => {
+                // Takes effect when the next file is loaded
+                self.flags.config.tone_mapping = tone_mapping;
+                return self.save_config();
+            }
+            Message::StyledSubtitles(styled_subtitles) => {
+                // Takes effect when the next file is loaded
+                self.flags.config.styled_subtitles = styled_subtitles;
+                return self.save_config();
+            }
+            Message::NormalizeVolume(normalize_volume) => {
+                self.flags.config.normalize_volume = normalize_volume;
+                return Command::batch([self.update_audio_filter(), self.save_config()]);
+            }
+            Message::ErrorDismiss => {
+                self.error_opt = None;
+                self.error_retry = false;
+            }
+            Message::LoadError(error) => {
+                self.error_opt = Some(error);
+                self.error_retry = false;
+                return self.update_title();
+            }
+            Message::NotifyOnFinish(notify_on_finish) => {
+                self.flags.config.notify_on_finish = notify_on_finish;
+                return self.save_config();
+            }
+            Message::NoticeDismiss => {
+                self.notice_opt = None;
+            }
+            Message::OpenInNewWindow(open_in_new_window) => {
+                self.flags.config.open_in_new_window = open_in_new_window;
+                return self.save_config();
+            }
+            Message::PauseOnUnfocus(pause_on_unfocus) => {
+                self.flags.config.pause_on_unfocus = pause_on_unfocus;
+                return self.save_config();
+            }
+            Message::WindowFocused(focused) => {
+                if let Some(video) = &mut self.video_opt {
+                    if focused {
+                        // Only undo a pause that losing focus caused
+                        if self.paused_on_unfocus && video.paused() {
+                            video.set_paused(false);
+                        }
+                        self.paused_on_unfocus = false;
+                    } else if self.flags.config.pause_on_unfocus && !video.paused() {
+                        video.set_paused(true);
+                        self.paused_on_unfocus = true;
+                        self.update_controls(true);
+                    }
+                }
+            }
             Message::AudioCode(code) => {
                 if let Ok(code) = i32::try_from(code) {
                     if let Some(video) = &self.video_opt {
@@ -482,58 +3735,282 @@ impl Application for App {
                         self.current_audio = pipeline.property("current-audio");
                     }
                 }
+                if let Some(Some(language)) = self.audio_languages.get(code) {
+                    self.flags.config.preferred_audio_language = Some(language.clone());
+                    return self.save_config();
+                }
+            }
+            Message::AudioDevice(index) => {
+                self.flags.config.audio_device = if index == 0 {
+                    None
+                } else {
+                    self.audio_devices.get(index).cloned()
+                };
+                // The audio sink can only be replaced when the pipeline is stopped
+                let audio_sink = self
+                    .flags
+                    .config
+                    .audio_device
+                    .as_deref()
+                    .and_then(audio_sink);
+                let restart = self.restart_pipeline(|pipeline| {
+                    pipeline.set_property("audio-sink", audio_sink.as_ref());
+                });
+                return Command::batch([restart, self.save_config()]);
             }
             Message::AudioToggle => {
                 if let Some(video) = &mut self.video_opt {
-                    video.set_muted(!video.muted());
+                    let muted = !video.muted();
+                    video.set_muted(muted);
                     self.update_controls(true);
+                    self.show_osd(if muted { fl!("muted") } else { fl!("unmuted") });
+                }
+            }
+            Message::TrackGain(gain) => {
+                let Some(url) = &self.flags.url_opt else {
+                    return Command::none();
+                };
+                self.flags
+                    .state
+                    .set_track_gain(url, Some(gain).filter(|&gain| gain != 0.0));
+                self.save_state();
+                match self
+                    .video_opt
+                    .as_ref()
+                    .and_then(|video| video.pipeline().by_name("track_gain"))
+                {
+                    Some(element) => element.set_property("volume", db_to_linear(gain)),
+                    // The gain element is only added once a file has a gain
+                    None if gain != 0.0 => return self.update_audio_filter(),
+                    None => {}
                 }
             }
             Message::AudioVolume(volume) => {
-                if let Some(video) = &mut self.video_opt {
-                    video.set_volume(volume);
+                if self.video_opt.is_some() {
+                    self.set_volume(volume);
                     self.update_controls(true);
                 }
             }
-            Message::TextCode(code) => {
-                if let Ok(code) = i32::try_from(code) {
-                    if let Some(video) = &self.video_opt {
-                        let pipeline = video.pipeline();
-                        pipeline.set_property("current-text", code);
-                        self.current_text = pipeline.property("current-text");
+            Message::AudioVolumeStep(step) => {
+                if self.video_opt.is_some() {
+                    self.set_volume(self.volume() + step);
+                    self.update_controls(true);
+                    self.show_osd(fl!(
+                        "volume-percent",
+                        percent = (self.volume() * 100.0).round()
+                    ));
+                }
+            }
+            Message::MaxVolume(max_volume) => {
+                self.flags.config.max_volume = max_volume;
+                // The boost element is only present while the maximum is above one, so the
+                // volume is limited to the new maximum once the pipeline has restarted
+                return Command::batch([self.update_audio_filter(), self.save_config()]);
+            }
+            Message::TextCode(index) => {
+                // Index zero is the off entry, the streams follow it
+                let enabled = index > 0;
+                let mut changed = self.flags.config.subtitles_enabled != enabled;
+                if changed || self.subtitles_forced_off {
+                    self.subtitles_forced_off = false;
+                    self.flags.config.subtitles_enabled = enabled;
+                    self.update_flags();
+                }
+                if let Some(code) = index.checked_sub(1) {
+                    if let Ok(code) = i32::try_from(code) {
+                        if let Some(video) = &self.video_opt {
+                            let pipeline = video.pipeline();
+                            pipeline.set_property("current-text", code);
+                            self.current_text = pipeline.property("current-text");
+                        }
+                    }
+                    if let Some(Some(language)) = self.text_languages.get(code) {
+                        self.flags.config.preferred_text_language = Some(language.clone());
+                        changed = true;
+                    }
+                }
+                if changed {
+                    return self.save_config();
+                }
+            }
+            Message::PipelineRestarted(id) => {
+                // Only the latest restart of the current file restores playback
+                if !self.restart_opt.is_some_and(|restart| restart.id == id) {
+                    return Command::none();
+                }
+                let (Some(restart), Some(video)) = (self.restart_opt.take(), &mut self.video_opt)
+                else {
+                    return Command::none();
+                };
+                if let Err(err) = video.seek(restart.position, true) {
+                    log::warn!("failed to restore position: {err}");
+                }
+                video.set_paused(restart.paused);
+                let pipeline = video.pipeline();
+                self.set_volume(restart.volume);
+                if restart.select_last_text {
+                    self.update_text_codes(&pipeline);
+                    // The external subtitle stream is added after embedded streams
+                    if let Some(index) = self.text_codes.len().checked_sub(1) {
+                        return self.update(Message::TextCode(index));
                     }
                 }
             }
+            Message::PlayFlag(flag, enabled) => {
+                let config = &mut self.flags.config;
+                match flag {
+                    GST_PLAY_FLAG_VIS => config.visualization = enabled,
+                    GST_PLAY_FLAG_SOFT_VOLUME => config.soft_volume = enabled,
+                    GST_PLAY_FLAG_SOFT_COLORBALANCE => config.soft_colorbalance = enabled,
+                    _ => {
+                        log::warn!("unsupported play flag {:#x}", flag);
+                        return Command::none();
+                    }
+                }
+                self.update_flags();
+                return self.save_config();
+            }
             Message::PlayPause => {
                 //TODO: cleanest way to close dropdowns
                 self.dropdown_opt = None;
+                self.paused_on_unfocus = false;
 
                 if let Some(video) = &mut self.video_opt {
                     video.set_paused(!video.paused());
                     self.update_controls(true);
                 }
             }
+            Message::Stop => {
+                // Pause at the start but leave the file loaded
+                if let Some(video) = &mut self.video_opt {
+                    video.set_paused(true);
+                    if let Err(err) = video.seek(Duration::ZERO, true) {
+                        log::warn!("failed to seek to start: {err}");
+                    }
+                    self.position = 0.0;
+                    self.update_controls(true);
+                }
+            }
+            Message::CopyFrame => {
+                if let Some(video) = &self.video_opt {
+                    let pipeline = video.pipeline();
+                    return Command::perform(
+                        async move {
+                            let result =
+                                tokio::task::spawn_blocking(move || match frame_png(&pipeline) {
+                                    Ok(data) => Some(data),
+                                    Err(err) => {
+                                        log::warn!("failed to copy frame: {}", err);
+                                        None
+                                    }
+                                })
+                                .await;
+                            match result {
+                                Ok(Some(data)) => {
+                                    message::app(Message::CopyFrameReady(ClipboardImage(data)))
+                                }
+                                Ok(None) => message::none(),
+                                Err(err) => {
+                                    log::warn!("failed to run copy frame task: {}", err);
+                                    message::none()
+                                }
+                            }
+                        },
+                        |x| x,
+                    );
+                }
+            }
+            Message::CopyFrameReady(image) => {
+                self.show_osd(fl!("frame-copied"));
+                return clipboard::write_data(image);
+            }
+            Message::CopyTimestamp => {
+                if self.video_opt.is_some() {
+                    let time = format_time(self.position);
+                    let text = match &self.flags.url_opt {
+                        Some(url) => format!("{} @ {}", url, time),
+                        None => time.clone(),
+                    };
+                    self.show_osd(fl!("timestamp-copied", time = time));
+                    return clipboard::write(text);
+                }
+            }
+            Message::Screenshot => {
+                if let Some(video) = &self.video_opt {
+                    let pipeline = video.pipeline();
+                    let name = self
+                        .media_title_opt
+                        .clone()
+                        .or_else(|| self.flags.url_opt.as_ref().map(url_name))
+                        .unwrap_or_else(|| "screenshot".to_string());
+                    let position = self.position;
+                    return Command::perform(
+                        async move {
+                            let result = tokio::task::spawn_blocking(move || {
+                                match screenshot(&pipeline, &name, position) {
+                                    Ok(path) => log::info!("saved screenshot to {:?}", path),
+                                    Err(err) => log::warn!("failed to save screenshot: {}", err),
+                                }
+                            })
+                            .await;
+                            if let Err(err) = result {
+                                log::warn!("failed to run screenshot task: {}", err);
+                            }
+                            message::none()
+                        },
+                        |x| x,
+                    );
+                }
+            }
             Message::Seek(secs) => {
                 //TODO: cleanest way to close dropdowns
                 self.dropdown_opt = None;
 
                 if let Some(video) = &mut self.video_opt {
-                    self.dragging = true;
+                    if !self.dragging {
+                        self.dragging = true;
+                        self.paused_on_scrub = video.paused();
+                        video.set_paused(true);
+                    }
                     self.position = secs;
-                    video.set_paused(true);
-                    let duration = Duration::try_from_secs_f64(self.position).unwrap_or_default();
-                    video.seek(duration, true).expect("seek");
+                    // Show keyframes while dragging, throttled so decoding can keep up
+                    if self.flags.config.scrub_preview
+                        && self.scrub_time.elapsed() >= SCRUB_INTERVAL
+                    {
+                        self.scrub_time = Instant::now();
+                        let duration =
+                            Duration::try_from_secs_f64(self.position).unwrap_or_default();
+                        if let Err(err) = video.seek(duration, false) {
+                            log::warn!("failed to seek for scrub preview: {err}");
+                        }
+                    }
                     self.update_controls(true);
                 }
             }
+            Message::SeekCoarse(direction) => {
+                return self.update(Message::SeekRelative(
+                    direction * self.flags.config.seek_step_coarse,
+                ));
+            }
+            Message::SeekFine(direction) => {
+                return self.update(Message::SeekRelative(
+                    direction * self.flags.config.seek_step_fine,
+                ));
+            }
             Message::SeekRelative(secs) => {
                 if let Some(video) = &mut self.video_opt {
                     self.position = video.position().as_secs_f64();
                     let duration =
                         Duration::try_from_secs_f64(self.position + secs).unwrap_or_default();
-                    video.seek(duration, true).expect("seek");
+                    if let Err(err) = video.seek(duration, true) {
+                        log::warn!("failed to seek to {:?}: {err}", duration);
+                        self.show_osd(fl!("seek-failed"));
+                    }
                 }
             }
+            Message::SeekHover(seek_hover_opt) => {
+                self.seek_hover_opt = seek_hover_opt;
+            }
             Message::SeekRelease => {
                 //TODO: cleanest way to close dropdowns
                 self.dropdown_opt = None;
@@ -541,18 +4018,218 @@ impl Application for App {
                 if let Some(video) = &mut self.video_opt {
                     self.dragging = false;
                     let duration = Duration::try_from_secs_f64(self.position).unwrap_or_default();
-                    video.seek(duration, true).expect("seek");
-                    video.set_paused(false);
+                    let seek_result = video.seek(duration, true);
+                    if let Err(err) = &seek_result {
+                        log::warn!("failed to seek to {:?}: {err}", duration);
+                        self.position = video.position().as_secs_f64();
+                    }
+                    video.set_paused(self.paused_on_scrub);
                     self.update_controls(true);
+                    if seek_result.is_err() {
+                        self.show_osd(fl!("seek-failed"));
+                    }
+                }
+            }
+            Message::SetLoopPointA => {
+                if self.video_opt.is_some() {
+                    self.loop_a = Some(self.position);
+                    if self.loop_b.is_some_and(|loop_b| loop_b <= self.position) {
+                        self.loop_b = None;
+                    }
+                    self.show_osd(fl!("loop-point-a", time = format_time(self.position)));
+                }
+            }
+            Message::SetLoopPointB => {
+                if self.video_opt.is_some() {
+                    if self.loop_a.is_some_and(|loop_a| loop_a >= self.position) {
+                        self.loop_a = None;
+                    }
+                    self.loop_b = Some(self.position);
+                    self.show_osd(fl!("loop-point-b", time = format_time(self.position)));
+                }
+            }
+            Message::ClearLoopPoints => {
+                if self.loop_a.is_some() || self.loop_b.is_some() {
+                    self.loop_a = None;
+                    self.loop_b = None;
+                    self.show_osd(fl!("loop-cleared"));
+                }
+            }
+            Message::SetSleepTimer(duration_opt) => {
+                self.sleep_timer_opt =
+                    duration_opt.map(|duration| SleepTimer::At(Instant::now() + duration));
+                self.show_osd(match duration_opt {
+                    Some(duration) => {
+                        fl!(
+                            "sleep-timer-set",
+                            time = format_time(duration.as_secs_f64())
+                        )
+                    }
+                    None => fl!("sleep-timer-cancelled"),
+                });
+            }
+            Message::SleepAtEndOfFile => {
+                self.sleep_timer_opt = Some(SleepTimer::EndOfFile);
+                self.show_osd(fl!("sleep-timer-end-of-file"));
+            }
+            Message::SetStreamVariant(stream_variant) => {
+                let Some(&connection_speed) = self.stream_variant_speeds.get(stream_variant) else {
+                    return Command::none();
+                };
+                self.connection_speed = connection_speed;
+                if let Some(video) = &self.video_opt {
+                    let pipeline = video.pipeline();
+                    pipeline.set_property("connection-speed", connection_speed);
+                    // Apply to demuxers that are already running
+                    for element in pipeline.iterate_recurse().into_iter().flatten() {
+                        if is_adaptive_demuxer(&element) {
+                            set_connection_speed(&element, connection_speed);
+                        }
+                    }
+                }
+            }
+            Message::AudioDelay(delta_ms) => {
+                if let Some(video) = &self.video_opt {
+                    self.audio_delay =
+                        (self.audio_delay + delta_ms).clamp(-MAX_AUDIO_DELAY, MAX_AUDIO_DELAY);
+                    // Positive offsets delay the audio relative to the video
+                    video
+                        .pipeline()
+                        .set_property("av-offset", self.audio_delay * 1_000_000);
+                    self.show_osd(fl!("audio-delay", delay = self.audio_delay));
+                }
+            }
+            Message::SubtitleDelay(delta_ms) => {
+                if let Some(video) = &self.video_opt {
+                    self.subtitle_delay += delta_ms;
+                    video
+                        .pipeline()
+                        .set_property("text-offset", self.subtitle_delay * 1_000_000);
+                    self.show_osd(fl!("subtitle-delay", delay = self.subtitle_delay));
+                }
+            }
+            Message::SubtitlePosition(subtitle_position) => {
+                self.flags.config.subtitle_position = subtitle_position;
+                self.update_elements();
+                return self.save_config();
+            }
+            Message::SubtitleSize(subtitle_size) => {
+                self.flags.config.subtitle_size = subtitle_size;
+                self.update_subtitle_font();
+                return self.save_config();
+            }
+            Message::ColorBalance(index, value) => {
+                let Some(channel_value) = self.flags.config.color_balance.get_mut(index) else {
+                    return Command::none();
+                };
+                *channel_value = value;
+                if let Some(video) = &self.video_opt {
+                    update_color_balance(&self.flags.config, &video.pipeline());
+                }
+                return self.save_config();
+            }
+            Message::ColorBalanceReset => {
+                self.flags.config.color_balance = [0.0; 4];
+                if let Some(video) = &self.video_opt {
+                    update_color_balance(&self.flags.config, &video.pipeline());
+                }
+                return self.save_config();
+            }
+            Message::EqualizerBand(band, gain) => {
+                let Some(band_gain) = self.flags.config.equalizer.get_mut(band) else {
+                    return Command::none();
+                };
+                *band_gain = gain;
+                match self
+                    .video_opt
+                    .as_ref()
+                    .and_then(|video| video.pipeline().by_name("equalizer"))
+                {
+                    Some(equalizer) => update_equalizer(&self.flags.config, &equalizer),
+                    None => {
+                        if gain != 0.0 {
+                            return Command::batch([
+                                self.update_audio_filter(),
+                                self.save_config(),
+                            ]);
+                        }
+                    }
                 }
+                return self.save_config();
             }
             Message::EndOfStream => {
                 println!("end of stream");
+                // A looping file normally restarts inside the player, but if the
+                // end is still reported, start over without reloading
+                if self.flags.config.looping {
+                    if let Some(video) = &mut self.video_opt {
+                        match video.seek(Duration::ZERO, true) {
+                            Ok(()) => {
+                                video.set_paused(false);
+                                self.position = 0.0;
+                                return Command::none();
+                            }
+                            Err(err) => log::warn!("failed to seek to start for loop: {err}"),
+                        }
+                    }
+                }
+                // Hold the last frame, paused at the end
+                if let Some(video) = &mut self.video_opt {
+                    video.set_paused(true);
+                    self.position = self.duration;
+                    self.update_controls(true);
+                }
+                // Finished files start from the beginning next time
+                self.save_position();
+                if self.sleep_timer_opt == Some(SleepTimer::EndOfFile) {
+                    self.sleep_timer_opt = None;
+                    self.show_osd(fl!("sleep-timer-expired"));
+                }
+                #[cfg(feature = "xdg-portal")]
+                if self.flags.config.notify_on_finish {
+                    let name = self
+                        .flags
+                        .url_opt
+                        .as_ref()
+                        .map(url_name)
+                        .unwrap_or_default();
+                    return Command::perform(
+                        async move {
+                            match notify_finished(name).await {
+                                Ok(true) => message::app(Message::Reload),
+                                Ok(false) => message::none(),
+                                Err(err) => {
+                                    log::warn!("failed to show notification: {}", err);
+                                    message::none()
+                                }
+                            }
+                        },
+                        |x| x,
+                    );
+                }
+            }
+            Message::LoadSubtitle(path) => {
+                let url = match url::Url::from_file_path(&path) {
+                    Ok(url) => url,
+                    Err(()) => {
+                        log::warn!("failed to convert {:?} to URL", path);
+                        return Command::none();
+                    }
+                };
+                // The suburi property is only read when the pipeline starts
+                let command = self.restart_pipeline(|pipeline| {
+                    pipeline.set_property("suburi", url.as_str());
+                });
+                if let Some(restart) = &mut self.restart_opt {
+                    restart.select_last_text = true;
+                }
+                return command;
             }
             Message::MissingPlugin(element) => {
                 if let Some(video) = &mut self.video_opt {
                     video.set_paused(true);
                 }
+                self.plugin_install_pending = true;
                 return Command::perform(
                     async move {
                         tokio::task::spawn_blocking(move || {
@@ -562,6 +4239,7 @@ impl Application for App {
                                     install_ctx
                                         .set_desktop_id(&format!("{}.desktop", Self::APP_ID));
                                     let install_detail = missing_plugin.installer_detail();
+                                    let description = missing_plugin.description().to_string();
                                     loop {
                                         // Wait for any prior installations to finish
                                         while gst_pbutils::missing_plugins::install_plugins_installation_in_progress() {
@@ -591,17 +4269,16 @@ impl Application for App {
                                             },
                                             _ => {
                                                 log::warn!("failed to install plugins: {status}");
-                                                break;
+                                                return message::app(Message::PluginInstallFailed(description));
                                             }
                                         }
                                     }
-
                                 }
                                 Err(err) => {
                                     log::warn!("failed to parse missing plugin message: {err}");
+                                    message::app(Message::PluginInstallFailed(err.to_string()))
                                 }
                             }
-                            message::none()
                         })
                         .await
                         .unwrap()
@@ -610,12 +4287,52 @@ impl Application for App {
                 );
             }
             Message::NewFrame => {
+                if self.buffering_percent_opt == Some(100) {
+                    self.buffering_percent_opt = None;
+                }
                 if let Some(video) = &self.video_opt {
                     if !self.dragging {
                         self.position = video.position().as_secs_f64();
                         self.update_controls(self.dropdown_opt.is_some());
                     }
                 }
+                self.update_duration();
+                if self.position_save_time.elapsed() > POSITION_SAVE_INTERVAL {
+                    self.save_position();
+                }
+                if let Some(SleepTimer::At(sleep_time)) = self.sleep_timer_opt {
+                    if Instant::now() >= sleep_time {
+                        self.sleep_timer_opt = None;
+                        if let Some(video) = &mut self.video_opt {
+                            video.set_paused(true);
+                        }
+                        self.update_controls(true);
+                        self.show_osd(fl!("sleep-timer-expired"));
+                    }
+                }
+                if let (Some(loop_a), Some(loop_b)) = (self.loop_a, self.loop_b) {
+                    if self.position >= loop_b {
+                        if let Some(video) = &mut self.video_opt {
+                            self.position = loop_a;
+                            let duration = Duration::try_from_secs_f64(loop_a).unwrap_or_default();
+                            if let Err(err) = video.seek(duration, true) {
+                                log::warn!("failed to seek to loop point: {err}");
+                            }
+                        }
+                    }
+                }
+                if self.flags.config.title_progress
+                    && self.progress_percent() != self.title_percent_opt
+                {
+                    return self.update_title();
+                }
+            }
+            Message::PluginInstallFailed(detail) => {
+                // Leave a message and a retry button instead of a blank video
+                self.close();
+                self.error_opt = Some(fl!("plugin-install-error", detail = detail));
+                self.error_retry = true;
+                return self.update_title();
             }
             Message::Reload => {
                 return self.load();
@@ -626,244 +4343,155 @@ impl Application for App {
             Message::SystemThemeModeChange(_theme_mode) => {
                 return self.update_config();
             }
+            Message::Tick => {
+                self.update_osd();
+                self.update_buffering();
+                self.update_stats();
+            }
+            Message::TitleProgress(title_progress) => {
+                self.flags.config.title_progress = title_progress;
+                return Command::batch([self.update_title(), self.save_config()]);
+            }
+            Message::ToggleContextPage(context_page) => {
+                if self.context_page == context_page {
+                    self.core.window.show_context = !self.core.window.show_context;
+                } else {
+                    self.context_page = context_page;
+                    self.core.window.show_context = true;
+                }
+                self.set_context_title(context_page.title());
+            }
+            Message::ToggleLoop => {
+                self.flags.config.looping = !self.flags.config.looping;
+                if let Some(video) = &mut self.video_opt {
+                    video.set_looping(self.flags.config.looping);
+                }
+                self.show_osd(if self.flags.config.looping {
+                    fl!("loop-on")
+                } else {
+                    fl!("loop-off")
+                });
+                return self.save_config();
+            }
+            Message::ToggleStats => {
+                self.stats_opt = match self.stats_opt {
+                    Some(_) => None,
+                    None => Some(Vec::new()),
+                };
+                self.update_stats();
+            }
+            Message::ControlsHover(controls_hover) => {
+                self.controls_hover = controls_hover;
+                self.update_controls(controls_hover);
+            }
+            Message::ControlsTimeout(controls_timeout) => {
+                self.flags.config.controls_timeout = controls_timeout;
+                self.update_controls(true);
+                return self.save_config();
+            }
+            Message::UpdateRate(update_rate) => {
+                self.flags.config.update_rate = update_rate;
+                return self.save_config();
+            }
+            Message::WindowResized(width, height) => {
+                // Only the windowed size is stored
+                if !self.fullscreen && !self.mini_player {
+                    // Save once resizing has settled
+                    self.window_resize_id = self.window_resize_id.wrapping_add(1);
+                    let window_resize_id = self.window_resize_id;
+                    return Command::perform(
+                        async move {
+                            tokio::time::sleep(WINDOW_SAVE_DELAY).await;
+                            message::app(Message::WindowSave(window_resize_id, width, height))
+                        },
+                        |x| x,
+                    );
+                }
+            }
+            Message::WindowSave(window_resize_id, width, height) => {
+                if window_resize_id == self.window_resize_id
+                    && (width, height)
+                        != (
+                            self.flags.config.window_width,
+                            self.flags.config.window_height,
+                        )
+                {
+                    self.flags.config.window_width = width;
+                    self.flags.config.window_height = height;
+                    return self.save_config();
+                }
+            }
             Message::WindowClose => {
+                // Stop the pipeline and save the position before exiting
+                self.close();
                 process::exit(0);
             }
         }
         Command::none()
     }
 
+    fn context_drawer(&self) -> Option<Element<Self::Message>> {
+        if !self.core.window.show_context {
+            return None;
+        }
+
+        Some(match self.context_page {
+            ContextPage::Settings => self.settings(),
+        })
+    }
+
     fn header_start(&self) -> Vec<Element<Self::Message>> {
-        vec![menu::menu_bar(&self.flags.config, &self.key_binds)]
+        vec![menu::menu_bar(
+            &self.flags.config,
+            &self.flags.state,
+            self.flags.url_opt.as_ref(),
+            &self.key_binds,
+        )]
     }
 
     /// Creates a view after each update.
     fn view(&self) -> Element<Self::Message> {
-        let cosmic_theme::Spacing {
-            space_xxs,
-            space_xs,
-            space_m,
-            ..
-        } = theme::active().cosmic().spacing;
-
-        let format_time = |time_float: f64| -> String {
-            let time = time_float.floor() as i64;
-            let seconds = time % 60;
-            let minutes = (time / 60) % 60;
-            let hours = (time / 60) / 60;
-            format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
-        };
-
-        let Some(video) = &self.video_opt else {
-            //TODO: open button if no video?
-            return widget::container(widget::text("No video open"))
-                .width(Length::Fill)
-                .height(Length::Fill)
-                .style(theme::Container::WindowBackground)
-                .into();
-        };
-
-        let muted = video.muted();
-        let volume = video.volume();
-
-        let video_player = VideoPlayer::new(video)
-            .mouse_hidden(!self.controls)
-            .on_end_of_stream(Message::EndOfStream)
-            .on_missing_plugin(Message::MissingPlugin)
-            .on_new_frame(Message::NewFrame)
-            .width(Length::Fill)
-            .height(Length::Fill);
-
-        let mouse_area = widget::mouse_area(video_player)
-            .on_press(Message::PlayPause)
-            .on_double_press(Message::Fullscreen);
-
-        let mut popover = widget::popover(mouse_area).position(widget::popover::Position::Bottom);
-        let mut popup_items = Vec::<Element<_>>::with_capacity(2);
-        if let Some(dropdown) = self.dropdown_opt {
-            let mut items = Vec::<Element<_>>::new();
-            match dropdown {
-                DropdownKind::Audio => {
-                    items.push(
-                        widget::row::with_children(vec![
-                            widget::button::icon(
-                                widget::icon::from_name({
-                                    if muted {
-                                        "audio-volume-muted-symbolic"
-                                    } else {
-                                        if volume >= (2.0 / 3.0) {
-                                            "audio-volume-high-symbolic"
-                                        } else if volume >= (1.0 / 3.0) {
-                                            "audio-volume-medium-symbolic"
-                                        } else {
-                                            "audio-volume-low-symbolic"
-                                        }
-                                    }
-                                })
-                                .size(16),
-                            )
-                            .on_press(Message::AudioToggle)
-                            .into(),
-                            //TODO: disable slider when muted?
-                            Slider::new(0.0..=1.0, volume, Message::AudioVolume)
-                                .step(0.01)
-                                .into(),
-                        ])
-                        .align_items(Alignment::Center)
-                        .into(),
-                    );
-                }
-                DropdownKind::Subtitle => {
-                    if !self.audio_codes.is_empty() {
-                        items.push(widget::text::heading(fl!("audio")).into());
-                        items.push(
-                            widget::dropdown(
-                                &self.audio_codes,
-                                usize::try_from(self.current_audio).ok(),
-                                Message::AudioCode,
-                            )
-                            .into(),
-                        );
-                    }
-                    if !self.text_codes.is_empty() {
-                        //TODO: allow toggling subtitles
-                        items.push(widget::text::heading(fl!("subtitles")).into());
-                        items.push(
-                            widget::dropdown(
-                                &self.text_codes,
-                                usize::try_from(self.current_text).ok(),
-                                Message::TextCode,
-                            )
-                            .into(),
-                        );
-                    }
-                }
-            }
-
-            let mut column = widget::column::with_capacity(items.len());
-            for item in items {
-                column = column.push(widget::container(item).padding([space_xxs, space_m]));
-            }
-
-            popup_items.push(
-                widget::row::with_children(vec![
-                    widget::horizontal_space(Length::Fill).into(),
-                    widget::container(column)
-                        .padding(1)
-                        //TODO: move style to libcosmic
-                        .style(theme::Container::custom(|theme| {
-                            let cosmic = theme.cosmic();
-                            let component = &cosmic.background.component;
-                            widget::container::Appearance {
-                                icon_color: Some(component.on.into()),
-                                text_color: Some(component.on.into()),
-                                background: Some(Background::Color(component.base.into())),
-                                border: Border {
-                                    radius: 8.0.into(),
-                                    width: 1.0,
-                                    color: component.divider.into(),
-                                },
-                                ..Default::default()
-                            }
-                        }))
-                        .width(Length::Fixed(240.0))
-                        .into(),
-                ])
-                .into(),
-            );
-        }
-        if self.controls {
-            popup_items.push(
-                widget::container(
-                    widget::row::with_capacity(7)
-                        .align_items(Alignment::Center)
-                        .spacing(space_xxs)
-                        .push(
-                            widget::button::icon(
-                                if self.video_opt.as_ref().map_or(true, |video| video.paused()) {
-                                    widget::icon::from_name("media-playback-start-symbolic")
-                                        .size(16)
-                                } else {
-                                    widget::icon::from_name("media-playback-pause-symbolic")
-                                        .size(16)
-                                },
-                            )
-                            .on_press(Message::PlayPause),
-                        )
-                        .push(widget::text(format_time(self.position)).font(font::mono()))
-                        .push(
-                            Slider::new(0.0..=self.duration, self.position, Message::Seek)
-                                .step(0.1)
-                                .on_release(Message::SeekRelease),
-                        )
-                        .push(
-                            widget::text(format_time(self.duration - self.position))
-                                .font(font::mono()),
-                        )
-                        .push(
-                            widget::button::icon(
-                                widget::icon::from_name("media-view-subtitles-symbolic").size(16),
-                            )
-                            .on_press(Message::DropdownToggle(DropdownKind::Subtitle)),
-                        )
-                        .push(
-                            widget::button::icon(
-                                widget::icon::from_name("view-fullscreen-symbolic").size(16),
-                            )
-                            .on_press(Message::Fullscreen),
-                        )
-                        .push(
-                            //TODO: scroll up/down on icon to change volume
-                            widget::button::icon(
-                                widget::icon::from_name({
-                                    if muted {
-                                        "audio-volume-muted-symbolic"
-                                    } else {
-                                        if volume >= (2.0 / 3.0) {
-                                            "audio-volume-high-symbolic"
-                                        } else if volume >= (1.0 / 3.0) {
-                                            "audio-volume-medium-symbolic"
-                                        } else {
-                                            "audio-volume-low-symbolic"
-                                        }
-                                    }
-                                })
-                                .size(16),
-                            )
-                            .on_press(Message::DropdownToggle(DropdownKind::Audio)),
-                        ),
-                )
-                .padding([space_xxs, space_xs])
-                .style(theme::Container::WindowBackground)
+        let content = self.view_player();
+        match self.url_dialog() {
+            Some(dialog) => widget::popover(content)
+                .popup(dialog)
+                .position(widget::popover::Position::Center)
                 .into(),
-            );
-        }
-        if !popup_items.is_empty() {
-            popover = popover.popup(widget::column::with_children(popup_items));
+            None => content,
         }
-
-        widget::container(popover)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .style(theme::Container::Custom(Box::new(|_theme| {
-                widget::container::Appearance::default().with_background(Color::BLACK)
-            })))
-            .into()
     }
 
     fn subscription(&self) -> Subscription<Self::Message> {
         struct ConfigSubscription;
         struct ThemeSubscription;
 
-        Subscription::batch([
-            event::listen_with(|event, _status| match event {
+        let mut subscriptions = vec![
+            event::listen_with(|event, status| match event {
                 Event::Keyboard(KeyEvent::KeyPressed { key, modifiers, .. }) => {
                     Some(Message::Key(modifiers, key))
                 }
                 Event::Mouse(MouseEvent::CursorMoved { .. }) => Some(Message::ShowControls),
+                // Scrolling that was not used by a widget, such as over the video
+                Event::Mouse(MouseEvent::WheelScrolled { delta })
+                    if status == event::Status::Ignored =>
+                {
+                    Some(Message::Scrolled(delta))
+                }
+                Event::Window(_id, window::Event::Resized { width, height }) => {
+                    Some(Message::WindowResized(width, height))
+                }
+                Event::Window(_id, window::Event::Focused) => Some(Message::WindowFocused(true)),
+                Event::Window(_id, window::Event::Unfocused) => Some(Message::WindowFocused(false)),
+                Event::Window(_id, window::Event::FileDropped(path)) => {
+                    if path.is_dir() {
+                        Some(Message::FolderLoad(path))
+                    } else {
+                        url::Url::from_file_path(&path).ok().map(Message::FileLoad)
+                    }
+                }
                 _ => None,
             }),
-            cosmic_config::config_subscription(
+            cosmic_config::config_subscription::<_, Config>(
                 TypeId::of::<ConfigSubscription>(),
                 Self::APP_ID.into(),
                 CONFIG_VERSION,
@@ -872,7 +4500,7 @@ impl Application for App {
                 if !update.errors.is_empty() {
                     log::debug!("errors loading config: {:?}", update.errors);
                 }
-                Message::SystemThemeModeChange(update.config)
+                Message::Config(update.config)
             }),
             cosmic_config::config_subscription::<_, cosmic_theme::ThemeMode>(
                 TypeId::of::<ThemeSubscription>(),
@@ -885,6 +4513,293 @@ impl Application for App {
                 }
                 Message::SystemThemeModeChange(update.config)
             }),
-        ])
+        ];
+
+        if self.osd_opt.is_some() || self.stats_opt.is_some() || self.network_stream {
+            subscriptions.push(time::every(Duration::from_millis(250)).map(|_| Message::Tick));
+        }
+
+        // Update position on a timer instead of on every frame
+        if self.video_opt.is_some() && self.flags.config.update_rate > 0 {
+            subscriptions.push(
+                time::every(Duration::from_secs_f64(
+                    1.0 / f64::from(self.flags.config.update_rate),
+                ))
+                .map(|_| Message::NewFrame),
+            );
+        }
+
+        Subscription::batch(subscriptions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mistyped_stream_tags_are_skipped() {
+        if let Err(err) = gst::init() {
+            eprintln!("skipping test, GStreamer is not available: {err}");
+            return;
+        }
+        let tags_opt = tags_from_value(&42i32.to_value(), "get-audio-tags", 3);
+        assert!(tags_opt.is_none());
+        assert_eq!(
+            stream_name(tags_opt.as_ref(), format!("Audio #{}", 3)),
+            "Audio #3"
+        );
+    }
+
+    #[test]
+    fn stream_tags_are_named() {
+        if let Err(err) = gst::init() {
+            eprintln!("skipping test, GStreamer is not available: {err}");
+            return;
+        }
+        assert!(tags_from_value(&None::<gst::TagList>.to_value(), "get-audio-tags", 0).is_none());
+
+        let mut tags = gst::TagList::new();
+        tags.make_mut()
+            .add::<gst::tags::Title>(&"Commentary", gst::TagMergeMode::Append);
+        let tags_opt = tags_from_value(&tags.to_value(), "get-audio-tags", 0);
+        assert_eq!(
+            stream_name(tags_opt.as_ref(), "Audio #0".to_string()),
+            "Commentary"
+        );
+    }
+
+    #[test]
+    fn problematic_codecs() {
+        if let Err(err) = gst::init() {
+            eprintln!("skipping test, GStreamer is not available: {err}");
+            return;
+        }
+        let vp9 = |profile: &str| {
+            gst::Caps::builder("video/x-vp9")
+                .field("profile", profile)
+                .build()
+        };
+        assert_eq!(
+            problematic_codec(&vp9("2")).as_deref(),
+            Some("VP9 profile 2")
+        );
+        assert_eq!(
+            problematic_codec(&vp9("3")).as_deref(),
+            Some("VP9 profile 3")
+        );
+        assert_eq!(problematic_codec(&vp9("0")), None);
+        assert_eq!(
+            problematic_codec(&gst::Caps::builder("video/x-vp9").build()),
+            None
+        );
+        let raw = gst::Caps::builder("video/x-raw")
+            .field("format", "I420")
+            .build();
+        assert_eq!(problematic_codec(&raw), None);
+    }
+
+    #[test]
+    fn sidecar_subtitle_keeps_dotted_name() {
+        let dir = env::temp_dir().join(format!("cosmic-player-{}-sidecar", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let video = dir.join("Show.S01E02.mkv");
+        for name in ["Show.srt", "Show.S01E01.srt", "Show.S01E02.srt"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        let subtitle_opt = sidecar_subtitle(&url::Url::from_file_path(&video).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            subtitle_opt,
+            url::Url::from_file_path(dir.join("Show.S01E02.srt")).ok()
+        );
+    }
+
+    #[test]
+    fn player_pipeline_loads_and_seeks() {
+        let Some(media) = probe::tests::test_media("player") else {
+            return;
+        };
+        for element in ["appsink", "videoflip", "videocrop", "volume", "rglimiter"] {
+            if gst::ElementFactory::find(element).is_none() {
+                eprintln!("skipping test, {element} is not available");
+                return;
+            }
+        }
+
+        let config = Config {
+            // Also build the audio filter
+            max_volume: MAX_VOLUMES[MAX_VOLUMES.len() - 1],
+            ..Config::default()
+        };
+        let flags = Flags {
+            config_handler: None,
+            config,
+            state_handler: None,
+            state: ConfigState::default(),
+            url_opt: Some(media.url.clone()),
+            start_opt: Some(Duration::from_millis(500)),
+            audio_track_opt: None,
+            text_track_opt: None,
+            fullscreen: false,
+        };
+        let (mut app, _command) = App::init(Core::default(), flags);
+
+        let pipeline = app
+            .video_opt
+            .as_ref()
+            .expect("failed to load test media")
+            .pipeline();
+        assert!(
+            (app.duration - 2.0).abs() < 0.1,
+            "duration is {}",
+            app.duration
+        );
+        assert_eq!(app.position, 0.5);
+        for name in ["flip", "zoom", "iced_video"] {
+            assert!(pipeline.by_name(name).is_some(), "{name} is missing");
+        }
+        assert!(pipeline
+            .property::<Option<gst::Element>>("audio-filter")
+            .is_some());
+        assert_eq!(app.audio_codes.len(), 1);
+
+        // Seek the way the seek keys do, while paused so the position stays put
+        if let Some(video) = &mut app.video_opt {
+            video.set_paused(true);
+        }
+        pipeline
+            .state(gst::ClockTime::from_seconds(5))
+            .0
+            .expect("failed to pause");
+        let _ = app.update(Message::SeekRelative(1.0));
+        pipeline
+            .state(gst::ClockTime::from_seconds(5))
+            .0
+            .expect("failed to finish seek");
+        let position = pipeline
+            .query_position::<gst::ClockTime>()
+            .expect("no position after seek")
+            .seconds_f64();
+        assert!(position > 1.4 && position < 1.7, "position is {position}");
+
+        app.close();
+        assert!(app.video_opt.is_none());
+    }
+
+    #[test]
+    fn subtitle_font_size_replaces_description_size() {
+        let config = |font: Option<&str>, size| Config {
+            subtitle_font: font.map(str::to_string),
+            subtitle_size: size,
+            ..Config::default()
+        };
+        assert_eq!(subtitle_font_desc(&config(None, 0)), None);
+        assert_eq!(
+            subtitle_font_desc(&config(Some("Sans 12"), 0)).as_deref(),
+            Some("Sans 12")
+        );
+        assert_eq!(
+            subtitle_font_desc(&config(Some("Sans 12"), 24)).as_deref(),
+            Some("Sans 24")
+        );
+        assert_eq!(
+            subtitle_font_desc(&config(Some("DejaVu Sans Bold 16px"), 24)).as_deref(),
+            Some("DejaVu Sans Bold 24")
+        );
+        assert_eq!(
+            subtitle_font_desc(&config(Some("Noto Serif"), 30)).as_deref(),
+            Some("Noto Serif 30")
+        );
+        assert_eq!(
+            subtitle_font_desc(&config(Some("12"), 30)).as_deref(),
+            Some("Sans 30")
+        );
+        assert_eq!(
+            subtitle_font_desc(&config(None, 30)).as_deref(),
+            Some("Sans 30")
+        );
+    }
+
+    #[test]
+    fn parse_time_formats() {
+        assert_eq!(parse_time("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_time("5.5"), Some(Duration::from_millis(5500)));
+        assert_eq!(parse_time(" 12 "), Some(Duration::from_secs(12)));
+        assert_eq!(parse_time("1:30"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_time("00:01:30"), Some(Duration::from_secs(90)));
+        assert_eq!(
+            parse_time("2:00:00.25"),
+            Some(Duration::from_millis(7_200_250))
+        );
+    }
+
+    #[test]
+    fn parse_time_rejects_invalid() {
+        for time in [
+            "", ":", "abc", "-5", "1:-5", "1.5:00", "00:75", "01:60:00", "1:2:3:4", "inf", "NaN",
+            "1e300",
+        ] {
+            assert_eq!(parse_time(time), None, "{time:?} should not parse");
+        }
+    }
+
+    fn write_temp_file(name: &str, data: &[u8]) -> PathBuf {
+        let path = env::temp_dir().join(format!("cosmic-player-{}-{name}", process::id()));
+        fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn opensubtitles_hash_sums_words() {
+        // 128 KiB: the first 64 KiB of ones and the last 64 KiB of twos, as LE words
+        let mut data = Vec::new();
+        for word in [1u64, 2] {
+            for _ in 0..8192 {
+                data.extend_from_slice(&word.to_le_bytes());
+            }
+        }
+        let path = write_temp_file("hash-words", &data);
+        let hash = opensubtitles_hash(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(hash.unwrap(), 131072 + 8192 + 8192 * 2);
+    }
+
+    #[test]
+    fn opensubtitles_hash_of_zeros_is_size() {
+        let path = write_temp_file("hash-zeros", &vec![0; 100_000]);
+        let hash = opensubtitles_hash(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(hash.unwrap(), 100_000);
+    }
+
+    #[test]
+    fn opensubtitles_hash_wraps() {
+        let path = write_temp_file("hash-wraps", &vec![0xff; 65536]);
+        let hash = opensubtitles_hash(&path);
+        fs::remove_file(&path).unwrap();
+        // Both chunks are the whole file: 2 * 8192 words of u64::MAX wrap to -16384
+        assert_eq!(hash.unwrap(), 65536u64.wrapping_sub(16384));
+    }
+
+    #[test]
+    fn opensubtitles_hash_rejects_small_files() {
+        let path = write_temp_file("hash-small", &[0; 1000]);
+        let hash = opensubtitles_hash(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(hash.is_err());
+    }
+
+    #[test]
+    fn opensubtitles_hash_reference() {
+        // The sample from the OpenSubtitles hash documentation is too large to vendor
+        let Some(path) = env::var_os("OPENSUBTITLES_HASH_SAMPLE") else {
+            eprintln!("skipping test, OPENSUBTITLES_HASH_SAMPLE is not set to breakdance.avi");
+            return;
+        };
+        assert_eq!(
+            format!("{:016x}", opensubtitles_hash(Path::new(&path)).unwrap()),
+            "8e245d9679d31e12"
+        );
     }
 }
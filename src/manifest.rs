@@ -0,0 +1,292 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+/// Codec prefixes of HLS CODECS attributes that are not video
+const HLS_AUDIO_CODECS: [&str; 9] = [
+    "mp4a", "ac-3", "ec-3", "ac-4", "opus", "Opus", "fLaC", "mp3", "alac",
+];
+
+/// A quality variant of an adaptive stream
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StreamVariant {
+    /// Bandwidth in bits per second
+    pub bandwidth: u64,
+    pub height_opt: Option<u32>,
+}
+
+impl StreamVariant {
+    /// Returns the connection speed in kbps at which demuxers pick this variant
+    pub fn connection_speed(&self) -> u64 {
+        self.bandwidth.div_ceil(1000)
+    }
+}
+
+/// Returns the video variants of an HLS, DASH or Smooth Streaming manifest, highest first
+pub fn variants(manifest: &str) -> Vec<StreamVariant> {
+    let manifest = manifest.trim_start_matches('\u{feff}').trim_start();
+    let mut variants = if manifest.starts_with("#EXTM3U") {
+        hls_variants(manifest)
+    } else {
+        xml_variants(manifest)
+    };
+    variants.sort_by_key(|variant| std::cmp::Reverse(variant.bandwidth));
+    variants.dedup_by_key(|variant| variant.connection_speed());
+    variants
+}
+
+/// Returns true unless the CODECS attribute lists only audio and subtitle codecs. Variants
+/// without CODECS are assumed to have video.
+fn hls_has_video(attributes: &str) -> bool {
+    let is_video = |codec: &str| {
+        let codec = codec.trim();
+        codec != "wvtt"
+            && !codec.starts_with("stpp")
+            && !HLS_AUDIO_CODECS.contains(&codec.split('.').next().unwrap_or(codec))
+    };
+    if hls_attribute(attributes, "RESOLUTION").is_some() {
+        return true;
+    }
+    match hls_attribute(attributes, "CODECS") {
+        Some(codecs) => codecs.split(',').any(is_video),
+        None => true,
+    }
+}
+
+/// Reads the video variant streams of an HLS master playlist
+fn hls_variants(playlist: &str) -> Vec<StreamVariant> {
+    playlist
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("#EXT-X-STREAM-INF:"))
+        .filter(|attributes| hls_has_video(attributes))
+        .filter_map(|attributes| {
+            Some(StreamVariant {
+                bandwidth: hls_attribute(attributes, "BANDWIDTH")?.parse().ok()?,
+                height_opt: hls_attribute(attributes, "RESOLUTION")
+                    .and_then(|resolution| resolution.split_once('x'))
+                    .and_then(|(_, height)| height.parse().ok()),
+            })
+        })
+        .collect()
+}
+
+/// Returns the value of an HLS attribute, without quotes
+fn hls_attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attributes;
+    while !rest.is_empty() {
+        let (key, value) = rest.split_once('=')?;
+        // Quoted values such as CODECS may contain commas
+        let (value, next) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"')?;
+                (&quoted[..end], quoted[end + 1..].trim_start_matches(','))
+            }
+            None => value.split_once(',').unwrap_or((value, "")),
+        };
+        if key.trim() == name {
+            return Some(value);
+        }
+        rest = next;
+    }
+    None
+}
+
+/// Reads the video representations of a DASH manifest or quality levels of a Smooth
+/// Streaming manifest
+fn xml_variants(manifest: &str) -> Vec<StreamVariant> {
+    let mut variants = Vec::new();
+    let mut video_set = false;
+    for tag in manifest.split('<').skip(1) {
+        let tag = tag.split('>').next().unwrap_or_default();
+        let name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        // Ignore namespace prefixes
+        let name = name.rsplit(':').next().unwrap_or(name);
+        match name {
+            "AdaptationSet" => {
+                video_set = xml_attribute(tag, "contentType") == Some("video")
+                    || xml_attribute(tag, "mimeType")
+                        .is_some_and(|mime_type| mime_type.starts_with("video/"));
+            }
+            "Representation" => {
+                let height_opt =
+                    xml_attribute(tag, "height").and_then(|height| height.parse().ok());
+                let video = video_set
+                    || height_opt.is_some()
+                    || xml_attribute(tag, "mimeType")
+                        .is_some_and(|mime_type| mime_type.starts_with("video/"));
+                if let Some(bandwidth) = xml_attribute(tag, "bandwidth")
+                    .and_then(|bandwidth| bandwidth.parse().ok())
+                    .filter(|_| video)
+                {
+                    variants.push(StreamVariant {
+                        bandwidth,
+                        height_opt,
+                    });
+                }
+            }
+            "StreamIndex" => {
+                video_set = xml_attribute(tag, "Type") == Some("video");
+            }
+            "QualityLevel" => {
+                if let Some(bandwidth) = xml_attribute(tag, "Bitrate")
+                    .and_then(|bitrate| bitrate.parse().ok())
+                    .filter(|_| video_set)
+                {
+                    variants.push(StreamVariant {
+                        bandwidth,
+                        height_opt: xml_attribute(tag, "MaxHeight")
+                            .and_then(|height| height.parse().ok()),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    variants
+}
+
+/// Returns the value of an XML attribute in the contents of a tag
+fn xml_attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!("{name}=");
+    let mut search = tag;
+    while let Some(index) = search.find(&pattern) {
+        let preceded_by_space = search[..index]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_whitespace);
+        let after = &search[index + pattern.len()..];
+        if preceded_by_space {
+            let quote = after.chars().next()?;
+            if quote == '"' || quote == '\'' {
+                let value = &after[1..];
+                return value.find(quote).map(|end| &value[..end]);
+            }
+        }
+        search = after;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hls_master_playlist() {
+        let playlist = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1280000,AVERAGE-BANDWIDTH=1000000,CODECS=\"avc1.4d401f,mp4a.40.2\",RESOLUTION=640x360\n\
+            low/index.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=5000000,RESOLUTION=1920x1080,CODECS=\"avc1.640028,mp4a.40.2\"\n\
+            high/index.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=64000,CODECS=\"mp4a.40.5\"\n\
+            audio/index.m3u8\n";
+        assert_eq!(
+            variants(playlist),
+            vec![
+                StreamVariant {
+                    bandwidth: 5000000,
+                    height_opt: Some(1080)
+                },
+                StreamVariant {
+                    bandwidth: 1280000,
+                    height_opt: Some(360)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn hls_variants_without_resolution() {
+        let playlist = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2000000,CODECS=\"hvc1.1.6.L93.B0,ec-3\"\n\
+            video/index.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=800000\n\
+            unknown/index.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=192000,CODECS=\"ec-3\"\n\
+            audio/index.m3u8\n";
+        assert_eq!(
+            variants(playlist),
+            vec![
+                StreamVariant {
+                    bandwidth: 2000000,
+                    height_opt: None
+                },
+                StreamVariant {
+                    bandwidth: 800000,
+                    height_opt: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn hls_media_playlist_has_no_variants() {
+        let playlist = "#EXTM3U\n#EXT-X-TARGETDURATION:10\n#EXTINF:9.009,\nsegment0.ts\n";
+        assert!(variants(playlist).is_empty());
+    }
+
+    #[test]
+    fn dash_video_representations() {
+        let manifest = r#"<?xml version="1.0"?>
+            <MPD xmlns="urn:mpeg:dash:schema:mpd:2011">
+              <Period>
+                <AdaptationSet contentType="video" mimeType="video/mp4">
+                  <Representation id="1" bandwidth="800000" width="640" height="360"/>
+                  <Representation id="2" bandwidth="3000000" width="1280" height="720"/>
+                </AdaptationSet>
+                <AdaptationSet mimeType="audio/mp4">
+                  <Representation id="3" bandwidth="128000"/>
+                </AdaptationSet>
+              </Period>
+            </MPD>"#;
+        assert_eq!(
+            variants(manifest),
+            vec![
+                StreamVariant {
+                    bandwidth: 3000000,
+                    height_opt: Some(720)
+                },
+                StreamVariant {
+                    bandwidth: 800000,
+                    height_opt: Some(360)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn smooth_streaming_quality_levels() {
+        let manifest = r#"<SmoothStreamingMedia MajorVersion="2" MinorVersion="0">
+              <StreamIndex Type="video" Chunks="2">
+                <QualityLevel Index="0" Bitrate="2000000" MaxWidth="1280" MaxHeight="720"/>
+                <QualityLevel Index="1" Bitrate="500000" MaxWidth="480" MaxHeight="270"/>
+              </StreamIndex>
+              <StreamIndex Type="audio" Chunks="2">
+                <QualityLevel Index="0" Bitrate="96000"/>
+              </StreamIndex>
+            </SmoothStreamingMedia>"#;
+        assert_eq!(
+            variants(manifest),
+            vec![
+                StreamVariant {
+                    bandwidth: 2000000,
+                    height_opt: Some(720)
+                },
+                StreamVariant {
+                    bandwidth: 500000,
+                    height_opt: Some(270)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn connection_speed_rounds_up() {
+        let variant = StreamVariant {
+            bandwidth: 1280001,
+            height_opt: None,
+        };
+        assert_eq!(variant.connection_speed(), 1281);
+    }
+}
@@ -0,0 +1,215 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+const EBML_ID: u32 = 0x1A45DFA3;
+const SEGMENT_ID: u32 = 0x18538067;
+const CLUSTER_ID: u32 = 0x1F43B675;
+const TIMECODE_ID: u32 = 0xE7;
+const BLOCK_GROUP_ID: u32 = 0xA0;
+const BLOCK_ID: u32 = 0xA1;
+const SIMPLE_BLOCK_ID: u32 = 0xA3;
+
+/// Number of blocks of the first cluster that are checked
+const MAX_BLOCKS: usize = 64;
+
+/// Returns true if blocks of the first cluster of a Matroska file start before time zero.
+/// GStreamer clamps such timestamps to zero, which breaks the order of the first frames.
+pub fn has_negative_timestamps<R: Read + Seek>(reader: &mut R) -> io::Result<bool> {
+    let (id, size_opt) = element_header(reader)?;
+    if id != EBML_ID {
+        return Err(invalid_data("not a Matroska file"));
+    }
+    skip(reader, size_opt)?;
+
+    let (id, _) = element_header(reader)?;
+    if id != SEGMENT_ID {
+        return Err(invalid_data("missing segment"));
+    }
+    // Skip the segment metadata before the first cluster
+    let cluster_size_opt = loop {
+        let (id, size_opt) = element_header(reader)?;
+        if id == CLUSTER_ID {
+            break size_opt;
+        }
+        skip(reader, size_opt)?;
+    };
+    let cluster_end_opt = match cluster_size_opt {
+        Some(size) => Some(reader.stream_position()? + size),
+        None => None,
+    };
+
+    let mut cluster_time = 0;
+    let mut blocks = 0;
+    while blocks < MAX_BLOCKS {
+        if let Some(end) = cluster_end_opt {
+            if reader.stream_position()? >= end {
+                break;
+            }
+        }
+        let (id, size_opt) = match element_header(reader) {
+            Ok(header) => header,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err),
+        };
+        match id {
+            TIMECODE_ID => {
+                let size = size_opt.ok_or_else(|| invalid_data("unknown timecode size"))?;
+                cluster_time = read_uint(reader, size)?;
+            }
+            // Blocks are the only children of a block group that are needed
+            BLOCK_GROUP_ID => {}
+            BLOCK_ID | SIMPLE_BLOCK_ID => {
+                let size = size_opt.ok_or_else(|| invalid_data("unknown block size"))?;
+                let start = reader.stream_position()?;
+                // The track number comes before the timecode relative to the cluster
+                read_vint(reader)?;
+                let mut relative_time = [0; 2];
+                reader.read_exact(&mut relative_time)?;
+                let relative_time = i16::from_be_bytes(relative_time);
+                if relative_time < 0 && cluster_time < u64::from(relative_time.unsigned_abs()) {
+                    return Ok(true);
+                }
+                reader.seek(SeekFrom::Start(start + size))?;
+                blocks += 1;
+            }
+            // A cluster of unknown size ends where the next one starts
+            CLUSTER_ID => break,
+            _ => skip(reader, size_opt)?,
+        }
+    }
+    Ok(false)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads a variable length integer, returning its value without the length marker and its length
+fn read_vint<R: Read>(reader: &mut R) -> io::Result<(u64, u32)> {
+    let mut first = [0];
+    reader.read_exact(&mut first)?;
+    let len = first[0].leading_zeros() + 1;
+    if len > 8 {
+        return Err(invalid_data("invalid variable length integer"));
+    }
+    let mut value = u64::from(first[0]) & (0xFF >> len);
+    for _ in 1..len {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+        value = (value << 8) | u64::from(byte[0]);
+    }
+    Ok((value, len))
+}
+
+/// Reads the ID and size of an element, where the size is `None` if it is unknown
+fn element_header<R: Read>(reader: &mut R) -> io::Result<(u32, Option<u64>)> {
+    let (id, id_len) = read_vint(reader)?;
+    if id_len > 4 {
+        return Err(invalid_data("invalid element ID"));
+    }
+    // IDs keep their length marker
+    let id = (id | (1 << (7 * id_len))) as u32;
+    let (size, size_len) = read_vint(reader)?;
+    // A size with all bits set is unknown
+    let size_opt = Some(size).filter(|&size| size != (1 << (7 * size_len)) - 1);
+    Ok((id, size_opt))
+}
+
+fn read_uint<R: Read>(reader: &mut R, size: u64) -> io::Result<u64> {
+    if size > 8 {
+        return Err(invalid_data("integer too large"));
+    }
+    let mut value = 0;
+    for _ in 0..size {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+        value = (value << 8) | u64::from(byte[0]);
+    }
+    Ok(value)
+}
+
+fn skip<R: Seek>(reader: &mut R, size_opt: Option<u64>) -> io::Result<()> {
+    let size = size_opt.ok_or_else(|| invalid_data("unknown element size"))?;
+    let offset = i64::try_from(size).map_err(|_| invalid_data("element too large"))?;
+    reader.seek(SeekFrom::Current(offset))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Encodes an element with a one byte size
+    fn element(id: &[u8], data: &[u8]) -> Vec<u8> {
+        assert!(data.len() < 0x7F);
+        let mut bytes = id.to_vec();
+        bytes.push(0x80 | data.len() as u8);
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn simple_block(track: u8, relative_time: i16) -> Vec<u8> {
+        let mut data = vec![0x80 | track];
+        data.extend_from_slice(&relative_time.to_be_bytes());
+        // Flags and a frame
+        data.extend_from_slice(&[0x80, 0, 0, 0]);
+        element(&[0xA3], &data)
+    }
+
+    fn file(cluster_time: u8, blocks: &[Vec<u8>], unknown_size: bool) -> Vec<u8> {
+        let mut cluster = element(&[0xE7], &[cluster_time]);
+        for block in blocks {
+            cluster.extend_from_slice(block);
+        }
+        let mut segment = element(&[0x15, 0x49, 0xA9, 0x66], &[0x2A, 0xD7, 0xB1, 0x83]);
+        if unknown_size {
+            segment.extend_from_slice(&[0x1F, 0x43, 0xB6, 0x75, 0xFF]);
+            segment.extend_from_slice(&cluster);
+        } else {
+            segment.extend_from_slice(&element(&[0x1F, 0x43, 0xB6, 0x75], &cluster));
+        }
+        let mut bytes = element(
+            &[0x1A, 0x45, 0xDF, 0xA3],
+            &element(&[0x42, 0x82], b"matroska"),
+        );
+        // The segment of a live recording has an unknown size
+        bytes.extend_from_slice(&[0x18, 0x53, 0x80, 0x67, 0x01, 0xFF, 0xFF, 0xFF]);
+        bytes.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF]);
+        bytes.extend_from_slice(&segment);
+        bytes
+    }
+
+    #[test]
+    fn positive_timestamps() {
+        let bytes = file(0, &[simple_block(1, 0), simple_block(2, 20)], false);
+        assert!(!has_negative_timestamps(&mut Cursor::new(bytes)).unwrap());
+    }
+
+    #[test]
+    fn negative_relative_timestamp_after_cluster_start() {
+        let bytes = file(50, &[simple_block(1, -40)], false);
+        assert!(!has_negative_timestamps(&mut Cursor::new(bytes)).unwrap());
+    }
+
+    #[test]
+    fn negative_timestamps() {
+        let bytes = file(0, &[simple_block(1, 0), simple_block(2, -13)], false);
+        assert!(has_negative_timestamps(&mut Cursor::new(bytes)).unwrap());
+    }
+
+    #[test]
+    fn negative_timestamps_in_block_group() {
+        let block = simple_block(2, -7);
+        // The same encoding with the ID of a block inside a group
+        let group = element(&[0xA0], &[&[0xA1][..], &block[1..]].concat());
+        let bytes = file(0, &[group], true);
+        assert!(has_negative_timestamps(&mut Cursor::new(bytes)).unwrap());
+    }
+
+    #[test]
+    fn not_matroska() {
+        assert!(has_negative_timestamps(&mut Cursor::new(b"RIFF\0\0\0\0AVI ".to_vec())).is_err());
+    }
+}
@@ -7,24 +7,144 @@ use cosmic::{
 };
 use std::collections::HashMap;
 
-use crate::{fl, Action, Config, Message};
+use crate::{fl, Action, Config, ConfigState, Message};
 
-pub fn menu_bar<'a>(config: &Config, key_binds: &HashMap<KeyBind, Action>) -> Element<'a, Message> {
-    let mut recent_items = Vec::new();
+/// Shows file URLs as paths relative to the home directory
+fn format_url(url: &url::Url) -> String {
+    let Ok(path) = url.to_file_path() else {
+        return url.to_string();
+    };
+    if let Some(home_dir) = dirs::home_dir() {
+        if let Ok(relative) = path.strip_prefix(&home_dir) {
+            return format!("~/{}", relative.display());
+        }
+    }
+    path.display().to_string()
+}
+
+pub fn menu_bar<'a>(
+    config: &Config,
+    state: &ConfigState,
+    url_opt: Option<&url::Url>,
+    key_binds: &HashMap<KeyBind, Action>,
+) -> Element<'a, Message> {
+    let recent_items = state
+        .recent_files
+        .iter()
+        .enumerate()
+        .map(|(index, recent_file)| {
+            menu::Item::Button(
+                recent_file
+                    .title_opt
+                    .clone()
+                    .unwrap_or_else(|| format_url(&recent_file.url)),
+                Action::FileOpenRecent(index),
+            )
+        })
+        .collect();
 
-    MenuBar::new(vec![menu::Tree::with_children(
-        menu::root(fl!("file")),
-        menu::items(
-            key_binds,
-            vec![
-                menu::Item::Button(fl!("open-media"), Action::FileOpen),
-                menu::Item::Folder(fl!("open-recent-media"), recent_items),
-                menu::Item::Button(fl!("close-file"), Action::FileClose),
-                menu::Item::Divider,
-                menu::Item::Button(fl!("quit"), Action::WindowClose),
-            ],
+    MenuBar::new(vec![
+        menu::Tree::with_children(
+            menu::root(fl!("file")),
+            menu::items(
+                key_binds,
+                vec![
+                    menu::Item::Button(fl!("open-media"), Action::FileOpen),
+                    menu::Item::Button(fl!("open-network-stream-menu"), Action::OpenUrl),
+                    menu::Item::Folder(fl!("open-recent-media"), recent_items),
+                    // Only local files have a folder to show
+                    if url_opt.is_some_and(|url| url.scheme() == "file") {
+                        menu::Item::Button(
+                            fl!("open-containing-folder"),
+                            Action::OpenContainingFolder,
+                        )
+                    } else {
+                        menu::Item::ButtonDisabled(
+                            fl!("open-containing-folder"),
+                            Action::OpenContainingFolder,
+                        )
+                    },
+                    menu::Item::Button(fl!("load-subtitles"), Action::SubtitleOpen),
+                    if config.subtitle_command.is_some() && url_opt.is_some() {
+                        menu::Item::Button(fl!("find-subtitles"), Action::RequestSubtitles)
+                    } else {
+                        menu::Item::ButtonDisabled(fl!("find-subtitles"), Action::RequestSubtitles)
+                    },
+                    if url_opt.is_some() {
+                        menu::Item::Button(fl!("reload-file"), Action::Reload)
+                    } else {
+                        menu::Item::ButtonDisabled(fl!("reload-file"), Action::Reload)
+                    },
+                    menu::Item::Button(fl!("close-file"), Action::FileClose),
+                    menu::Item::Divider,
+                    menu::Item::Button(fl!("quit"), Action::WindowClose),
+                ],
+            ),
+        ),
+        menu::Tree::with_children(
+            menu::root(fl!("playback")),
+            menu::items(
+                key_binds,
+                vec![
+                    menu::Item::Button(fl!("stop"), Action::Stop),
+                    menu::Item::Divider,
+                    menu::Item::Button(fl!("copy-timestamp"), Action::CopyTimestamp),
+                    menu::Item::Button(fl!("copy-frame"), Action::CopyFrame),
+                    menu::Item::Divider,
+                    menu::Item::Folder(
+                        fl!("sleep-timer"),
+                        vec![
+                            menu::Item::Button(
+                                fl!("sleep-timer-minutes", minutes = 15),
+                                Action::SleepTimer15,
+                            ),
+                            menu::Item::Button(
+                                fl!("sleep-timer-minutes", minutes = 30),
+                                Action::SleepTimer30,
+                            ),
+                            menu::Item::Button(
+                                fl!("sleep-timer-minutes", minutes = 60),
+                                Action::SleepTimer60,
+                            ),
+                            menu::Item::Button(
+                                fl!("sleep-timer-end-of-file-short"),
+                                Action::SleepTimerEndOfFile,
+                            ),
+                            menu::Item::Button(fl!("sleep-timer-off"), Action::SleepTimerOff),
+                        ],
+                    ),
+                ],
+            ),
+        ),
+        menu::Tree::with_children(
+            menu::root(fl!("view")),
+            menu::items(
+                key_binds,
+                vec![
+                    menu::Item::Folder(
+                        fl!("deinterlace"),
+                        vec![
+                            menu::Item::Button(fl!("deinterlace-auto"), Action::DeinterlaceAuto),
+                            menu::Item::Button(fl!("deinterlace-on"), Action::DeinterlaceOn),
+                            menu::Item::Button(fl!("deinterlace-off"), Action::DeinterlaceOff),
+                        ],
+                    ),
+                    menu::Item::Button(fl!("menu-color-balance"), Action::ColorBalance),
+                    menu::Item::Button(fl!("mini-player"), Action::MiniPlayer),
+                    menu::Item::Folder(
+                        fl!("zoom"),
+                        vec![
+                            menu::Item::Button(fl!("zoom-in"), Action::ZoomIn),
+                            menu::Item::Button(fl!("zoom-out"), Action::ZoomOut),
+                            menu::Item::Button(fl!("zoom-reset"), Action::ZoomReset),
+                        ],
+                    ),
+                    menu::Item::Divider,
+                    menu::Item::Button(fl!("menu-settings"), Action::Settings),
+                ],
+            ),
         ),
-    )])
+    ])
     .item_height(ItemHeight::Dynamic(40))
     .item_width(ItemWidth::Uniform(240))
     .spacing(theme::active().cosmic().spacing.space_xxxs.into())
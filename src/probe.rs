@@ -0,0 +1,286 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use iced_video_player::gst::{self, prelude::*};
+use serde::Serialize;
+use std::error::Error;
+
+use crate::stream_tags;
+
+/// Seconds to wait for the pipeline to preroll
+const PROBE_TIMEOUT: u64 = 10;
+
+#[derive(Debug, Default, Serialize)]
+pub struct MediaInfo {
+    pub uri: String,
+    pub container: Option<String>,
+    /// Duration in seconds, if known
+    pub duration: Option<f64>,
+    pub video: Vec<VideoInfo>,
+    pub audio: Vec<AudioInfo>,
+    pub subtitles: Vec<SubtitleInfo>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct VideoInfo {
+    pub codec: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct AudioInfo {
+    pub codec: Option<String>,
+    pub language: Option<String>,
+    pub channels: Option<i32>,
+    pub rate: Option<i32>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SubtitleInfo {
+    pub codec: Option<String>,
+    pub language: Option<String>,
+}
+
+/// Returns the caps structure of the pad for a stream
+fn stream_caps(pipeline: &gst::Pipeline, signal: &str, index: i32) -> Option<gst::Structure> {
+    let pad = pipeline.emit_by_name::<Option<gst::Pad>>(signal, &[&index])?;
+    let caps = pad.current_caps()?;
+    caps.structure(0).map(|structure| structure.to_owned())
+}
+
+/// Returns a string tag such as `"video-codec"`
+fn tag_string(tags_opt: &Option<gst::TagList>, name: &str) -> Option<String> {
+    tags_opt
+        .as_ref()
+        .and_then(|tags| tags.generic(name))
+        .and_then(|value| value.get::<String>().ok())
+}
+
+/// Prerolls the media with fake sinks and reads its streams
+pub fn probe(url: &url::Url) -> Result<MediaInfo, Box<dyn Error>> {
+    let pipeline = preroll(url)?;
+    let info = media_info(&pipeline, url);
+    pipeline.set_state(gst::State::Null)?;
+    Ok(info)
+}
+
+/// Waits until the pipeline has finished changing state, or fails on an error
+fn wait_async_done(pipeline: &gst::Pipeline) -> Result<(), Box<dyn Error>> {
+    let bus = pipeline.bus().ok_or("pipeline has no bus")?;
+    loop {
+        let Some(message) = bus.timed_pop_filtered(
+            gst::ClockTime::from_seconds(PROBE_TIMEOUT),
+            &[gst::MessageType::AsyncDone, gst::MessageType::Error],
+        ) else {
+            return Err("timed out waiting for media".into());
+        };
+        match message.view() {
+            gst::MessageView::AsyncDone(_) => return Ok(()),
+            gst::MessageView::Error(err) => return Err(err.error().into()),
+            _ => {}
+        }
+    }
+}
+
+/// Creates a playbin with fake sinks and pauses it once the media is ready
+fn preroll(url: &url::Url) -> Result<gst::Pipeline, Box<dyn Error>> {
+    gst::init()?;
+
+    let pipeline = gst::ElementFactory::make("playbin")
+        .property("uri", url.as_str())
+        .property("video-sink", gst::ElementFactory::make("fakesink").build()?)
+        .property("audio-sink", gst::ElementFactory::make("fakesink").build()?)
+        .build()?
+        .downcast::<gst::Pipeline>()
+        .map_err(|_| "playbin is not a pipeline")?;
+
+    let result = pipeline
+        .set_state(gst::State::Paused)
+        .map_err(|err| err.into())
+        .and_then(|_| wait_async_done(&pipeline));
+    match result {
+        Ok(()) => Ok(pipeline),
+        Err(err) => {
+            pipeline.set_state(gst::State::Null)?;
+            Err(err)
+        }
+    }
+}
+
+fn media_info(pipeline: &gst::Pipeline, url: &url::Url) -> MediaInfo {
+    let mut info = MediaInfo {
+        uri: url.to_string(),
+        duration: pipeline
+            .query_duration::<gst::ClockTime>()
+            .map(|duration| duration.seconds_f64()),
+        ..Default::default()
+    };
+
+    for i in 0..pipeline.property::<i32>("n-video") {
+        let tags_opt = stream_tags(pipeline, "get-video-tags", i);
+        let caps_opt = stream_caps(pipeline, "get-video-pad", i);
+        info.container = info
+            .container
+            .or_else(|| tag_string(&tags_opt, "container-format"));
+        info.video.push(VideoInfo {
+            codec: tag_string(&tags_opt, "video-codec"),
+            width: caps_opt.as_ref().and_then(|caps| caps.get("width").ok()),
+            height: caps_opt.as_ref().and_then(|caps| caps.get("height").ok()),
+        });
+    }
+
+    for i in 0..pipeline.property::<i32>("n-audio") {
+        let tags_opt = stream_tags(pipeline, "get-audio-tags", i);
+        let caps_opt = stream_caps(pipeline, "get-audio-pad", i);
+        info.container = info
+            .container
+            .or_else(|| tag_string(&tags_opt, "container-format"));
+        info.audio.push(AudioInfo {
+            codec: tag_string(&tags_opt, "audio-codec"),
+            language: tag_string(&tags_opt, "language-code"),
+            channels: caps_opt.as_ref().and_then(|caps| caps.get("channels").ok()),
+            rate: caps_opt.as_ref().and_then(|caps| caps.get("rate").ok()),
+        });
+    }
+
+    for i in 0..pipeline.property::<i32>("n-text") {
+        let tags_opt = stream_tags(pipeline, "get-text-tags", i);
+        info.subtitles.push(SubtitleInfo {
+            codec: tag_string(&tags_opt, "subtitle-codec"),
+            language: tag_string(&tags_opt, "language-code"),
+        });
+    }
+
+    info
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::{env, fs, path::PathBuf, process};
+
+    /// Elements needed to encode the test media
+    const TEST_ELEMENTS: [&str; 7] = [
+        "videotestsrc",
+        "audiotestsrc",
+        "theoraenc",
+        "vorbisenc",
+        "oggmux",
+        "filesink",
+        "playbin",
+    ];
+
+    /// Test media that is removed when dropped
+    pub(crate) struct TestMedia {
+        path: PathBuf,
+        pub(crate) url: url::Url,
+    }
+
+    impl Drop for TestMedia {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    /// Encodes two seconds of 320x240 video and stereo audio to an Ogg file, or returns
+    /// `None` if GStreamer or one of its plugins is not available
+    pub(crate) fn test_media(name: &str) -> Option<TestMedia> {
+        if let Err(err) = gst::init() {
+            eprintln!("skipping test, GStreamer is not available: {err}");
+            return None;
+        }
+        for element in TEST_ELEMENTS {
+            if gst::ElementFactory::find(element).is_none() {
+                eprintln!("skipping test, {element} is not available");
+                return None;
+            }
+        }
+
+        let path = env::temp_dir().join(format!("cosmic-player-{}-{name}.ogg", process::id()));
+        let pipeline = gst::parse::launch(&format!(
+            "videotestsrc num-buffers=60 ! video/x-raw,width=320,height=240,framerate=30/1 ! theoraenc ! oggmux name=mux ! filesink location=\"{}\" \
+             audiotestsrc num-buffers=100 samplesperbuffer=882 ! audio/x-raw,rate=44100,channels=2 ! audioconvert ! vorbisenc ! mux.",
+            path.display()
+        ))
+        .expect("failed to create encoding pipeline");
+        let media = TestMedia {
+            url: url::Url::from_file_path(&path).expect("temporary path is not absolute"),
+            path,
+        };
+
+        pipeline
+            .set_state(gst::State::Playing)
+            .expect("failed to start encoding");
+        let bus = pipeline.bus().expect("encoding pipeline has no bus");
+        let message = bus
+            .timed_pop_filtered(
+                gst::ClockTime::from_seconds(PROBE_TIMEOUT),
+                &[gst::MessageType::Eos, gst::MessageType::Error],
+            )
+            .expect("timed out encoding test media");
+        if let gst::MessageView::Error(err) = message.view() {
+            panic!("failed to encode test media: {}", err.error());
+        }
+        pipeline
+            .set_state(gst::State::Null)
+            .expect("failed to stop encoding");
+        Some(media)
+    }
+
+    #[test]
+    fn probe_lists_streams() {
+        let Some(media) = test_media("streams") else {
+            return;
+        };
+        let info = probe(&media.url).expect("failed to probe test media");
+
+        let duration = info.duration.expect("no duration");
+        assert!((duration - 2.0).abs() < 0.1, "duration is {duration}");
+
+        assert_eq!(info.video.len(), 1);
+        assert_eq!(info.video[0].width, Some(320));
+        assert_eq!(info.video[0].height, Some(240));
+
+        assert_eq!(info.audio.len(), 1);
+        assert_eq!(info.audio[0].channels, Some(2));
+        assert_eq!(info.audio[0].rate, Some(44100));
+
+        assert!(info.subtitles.is_empty());
+    }
+
+    #[test]
+    fn preroll_seeks() {
+        let Some(media) = test_media("seek") else {
+            return;
+        };
+        let pipeline = preroll(&media.url).expect("failed to preroll test media");
+
+        let target = gst::ClockTime::from_seconds(1);
+        pipeline
+            .seek_simple(gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE, target)
+            .expect("failed to seek");
+        wait_async_done(&pipeline).expect("failed to finish seek");
+        let position = pipeline
+            .query_position::<gst::ClockTime>()
+            .expect("no position after seek");
+        assert!(
+            position.seconds_f64() > 0.9 && position.seconds_f64() < 1.1,
+            "position is {position}"
+        );
+
+        pipeline
+            .set_state(gst::State::Null)
+            .expect("failed to stop pipeline");
+    }
+
+    #[test]
+    fn probe_fails_for_missing_file() {
+        if gst::init().is_err() || gst::ElementFactory::find("playbin").is_none() {
+            eprintln!("skipping test, playbin is not available");
+            return;
+        }
+        let path = env::temp_dir().join(format!("cosmic-player-{}-missing.ogg", process::id()));
+        let url = url::Url::from_file_path(path).expect("temporary path is not absolute");
+        assert!(probe(&url).is_err());
+    }
+}